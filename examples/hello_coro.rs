@@ -1,9 +1,15 @@
-use exec::just;
+use exec::{just, then, IntoAwaitable};
 use futures::executor::block_on;
 
 fn main() {
     block_on(async {
         let sender = just(13);
         println!("result = {}", sender.await.unwrap().unwrap());
+
+        let sender = then(just(13), |x| x + 1);
+        println!(
+            "result = {}",
+            sender.into_awaitable().await.unwrap().unwrap()
+        );
     })
 }
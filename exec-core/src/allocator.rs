@@ -0,0 +1,236 @@
+//! Allocation of operations that outlive the call starting them.
+//!
+//! [`Global`] forwards to the global allocator, a [`SlabAllocator`] keeps
+//! released blocks around so that operations of the same type submitted over
+//! and over don't allocate once the pool is warm. [`SmallBlocks`] does the
+//! same for all small operations, through a cache of the current thread.
+
+use std::alloc::{self, Layout};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+
+/// # Safety
+///
+/// `allocate` must return a block valid for `layout` until it is passed back
+/// to `deallocate` on the same allocator or one of its clones.
+pub unsafe trait Allocator: Clone {
+    fn allocate(&self, layout: Layout) -> NonNull<u8>;
+
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `allocate` with the same `layout`.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+/// The global allocator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
+
+unsafe impl Allocator for Global {
+    fn allocate(&self, layout: Layout) -> NonNull<u8> {
+        assert_ne!(layout.size(), 0, "zero-sized allocation");
+        let ptr = unsafe { alloc::alloc(layout) };
+        NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        alloc::dealloc(ptr.as_ptr(), layout);
+    }
+}
+
+/// Pools released blocks by layout, clones share the pool.
+#[derive(Clone, Default)]
+pub struct SlabAllocator {
+    slab: Arc<Mutex<Slab>>,
+}
+
+#[derive(Default)]
+struct Slab {
+    free: HashMap<Layout, Vec<NonNull<u8>>>,
+    live: usize,
+}
+
+// The pooled blocks are owned by the slab.
+unsafe impl Send for Slab {}
+
+impl SlabAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of blocks currently handed out.
+    pub fn live(&self) -> usize {
+        self.slab.lock().unwrap().live
+    }
+
+    /// Number of released blocks kept for reuse.
+    pub fn pooled(&self) -> usize {
+        self.slab.lock().unwrap().free.values().map(Vec::len).sum()
+    }
+}
+
+unsafe impl Allocator for SlabAllocator {
+    fn allocate(&self, layout: Layout) -> NonNull<u8> {
+        let mut slab = self.slab.lock().unwrap();
+        slab.live += 1;
+        match slab.free.get_mut(&layout).and_then(Vec::pop) {
+            Some(ptr) => ptr,
+            None => Global.allocate(layout),
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let mut slab = self.slab.lock().unwrap();
+        slab.live -= 1;
+        slab.free.entry(layout).or_default().push(ptr);
+    }
+}
+
+impl Drop for Slab {
+    fn drop(&mut self) {
+        for (layout, blocks) in self.free.drain() {
+            for ptr in blocks {
+                unsafe { Global.deallocate(ptr, layout) };
+            }
+        }
+    }
+}
+
+/// Size classes of the blocks [`SmallBlocks`] caches, all aligned to
+/// `SMALL_ALIGN`.
+const SMALL_CLASSES: [usize; 4] = [64, 128, 256, 512];
+const SMALL_ALIGN: usize = 16;
+/// Blocks cached per class and thread, more go back to the global allocator.
+const SMALL_CACHED: usize = 64;
+
+thread_local! {
+    static SMALL_CACHE: RefCell<SmallCache> = RefCell::new(SmallCache::default());
+}
+
+#[derive(Default)]
+struct SmallCache {
+    free: [Vec<NonNull<u8>>; SMALL_CLASSES.len()],
+}
+
+impl Drop for SmallCache {
+    fn drop(&mut self) {
+        for (class, blocks) in self.free.iter_mut().enumerate() {
+            for ptr in blocks.drain(..) {
+                unsafe { Global.deallocate(ptr, SmallBlocks::class_layout(class)) };
+            }
+        }
+    }
+}
+
+/// Recycles small blocks through a cache of the current thread, larger or
+/// over-aligned ones go to [`Global`].
+///
+/// Blocks are rounded up to a few size classes. A block released on another
+/// thread than the one it was allocated on joins the cache of that thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmallBlocks;
+
+impl SmallBlocks {
+    /// Number of blocks cached by the current thread.
+    pub fn cached() -> usize {
+        SMALL_CACHE
+            .try_with(|cache| cache.borrow().free.iter().map(Vec::len).sum())
+            .unwrap_or(0)
+    }
+
+    fn class(layout: Layout) -> Option<usize> {
+        if layout.align() > SMALL_ALIGN {
+            return None;
+        }
+        SMALL_CLASSES.iter().position(|&size| layout.size() <= size)
+    }
+
+    fn class_layout(class: usize) -> Layout {
+        Layout::from_size_align(SMALL_CLASSES[class], SMALL_ALIGN).unwrap()
+    }
+}
+
+unsafe impl Allocator for SmallBlocks {
+    fn allocate(&self, layout: Layout) -> NonNull<u8> {
+        let Some(class) = Self::class(layout) else {
+            return Global.allocate(layout);
+        };
+        let cached = SMALL_CACHE
+            .try_with(|cache| cache.borrow_mut().free[class].pop())
+            .ok()
+            .flatten();
+        cached.unwrap_or_else(|| Global.allocate(Self::class_layout(class)))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let Some(class) = Self::class(layout) else {
+            return Global.deallocate(ptr, layout);
+        };
+        // The cache is gone when the thread is exiting.
+        let cached = SMALL_CACHE
+            .try_with(|cache| {
+                let free = &mut cache.borrow_mut().free[class];
+                let cached = free.len() < SMALL_CACHED;
+                if cached {
+                    free.push(ptr);
+                }
+                cached
+            })
+            .unwrap_or(false);
+        if !cached {
+            Global.deallocate(ptr, Self::class_layout(class));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slab_reuses_blocks() {
+        let allocator = SlabAllocator::new();
+        let layout = Layout::new::<[u64; 4]>();
+
+        let first = allocator.allocate(layout);
+        assert_eq!(allocator.live(), 1);
+        unsafe { allocator.clone().deallocate(first, layout) };
+        assert_eq!((allocator.live(), allocator.pooled()), (0, 1));
+
+        let second = allocator.allocate(layout);
+        assert_eq!(first, second);
+        assert_eq!((allocator.live(), allocator.pooled()), (1, 0));
+
+        let other = allocator.allocate(Layout::new::<u8>());
+        assert_ne!(other, second);
+        unsafe {
+            allocator.deallocate(second, layout);
+            allocator.deallocate(other, Layout::new::<u8>());
+        }
+        assert_eq!(allocator.pooled(), 2);
+    }
+
+    #[test]
+    fn test_small_blocks_reuse_blocks() {
+        let layout = Layout::new::<[u64; 4]>();
+        let first = SmallBlocks.allocate(layout);
+        unsafe { SmallBlocks.deallocate(first, layout) };
+        let cached = SmallBlocks::cached();
+        assert!(cached > 0);
+
+        // Same size class.
+        let second = SmallBlocks.allocate(Layout::new::<[u64; 6]>());
+        assert_eq!(first, second);
+        assert_eq!(SmallBlocks::cached(), cached - 1);
+
+        let large = Layout::new::<[u64; 128]>();
+        let other = SmallBlocks.allocate(large);
+        unsafe {
+            SmallBlocks.deallocate(other, large);
+            SmallBlocks.deallocate(second, Layout::new::<[u64; 6]>());
+        }
+        assert_eq!(SmallBlocks::cached(), cached);
+    }
+}
@@ -1,10 +1,21 @@
+pub mod allocator;
+pub use allocator::Allocator;
+
 mod operation_state;
 pub use operation_state::OperationState;
 
+pub mod panic;
+
 pub mod receiver;
 
 mod sender;
 pub use sender::Sender;
 
+pub mod sequence;
+pub use sequence::SequenceSender;
+
 mod scheduler;
-pub use scheduler::Scheduler;
+pub use scheduler::{Scheduler, TimedScheduler};
+
+pub mod stop_token;
+pub use stop_token::{StopCallback, StopSource, StopToken};
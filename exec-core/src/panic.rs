@@ -0,0 +1,188 @@
+//! Delivery of panics to the receivers they unwound through.
+//!
+//! A receiver dropped by a panic cannot complete on the spot, the payload is
+//! only known once the panic has been caught. It registers a handler with
+//! [`on_unwind`] instead, and the closest [`catch_unwind`] up the stack passes
+//! the payload to it.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+pub type Payload = Box<dyn Any + Send>;
+
+type Handler = Box<dyn FnOnce(Payload)>;
+
+thread_local! {
+    static HANDLERS: RefCell<Vec<Handler>> = const { RefCell::new(Vec::new()) };
+    /// How many [`catch_unwind`] calls are on this thread's stack.
+    static FRAMES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Registers `handler` to receive the payload of the panic unwinding the
+/// current thread.
+///
+/// Without a [`catch_unwind`] up the stack, nothing will ever have the
+/// payload to pass on, so `handler` is dropped right away.
+pub fn on_unwind<F>(handler: F)
+where
+    F: FnOnce(Payload) + 'static,
+{
+    if FRAMES.get() > 0 {
+        HANDLERS.with_borrow_mut(|handlers| handlers.push(Box::new(handler)));
+    }
+}
+
+/// Runs `f`, catching a panic and passing its payload to the handlers
+/// registered while unwinding.
+///
+/// Returns `Err(None)` if the panic has been delivered, `Err(Some(payload))`
+/// if nobody registered for it. The first handler gets the payload itself,
+/// any other one a copy of its message.
+///
+/// Handlers left behind by a panic caught by someone else within `f`, e.g.
+/// by [`std::panic::catch_unwind`], are dropped on return rather than given
+/// the payload of a later panic.
+pub fn catch_unwind<T>(f: impl FnOnce() -> T) -> Result<T, Option<Payload>> {
+    let depth = HANDLERS.with_borrow(Vec::len);
+    FRAMES.set(FRAMES.get() + 1);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    FRAMES.set(FRAMES.get() - 1);
+
+    let handlers = HANDLERS.with_borrow_mut(|handlers| handlers.split_off(depth));
+    let payload = match result {
+        Ok(value) => return Ok(value),
+        Err(payload) => payload,
+    };
+    if handlers.is_empty() {
+        return Err(Some(payload));
+    }
+
+    let message = message(&payload).unwrap_or_default().to_owned();
+    let mut payload = Some(payload);
+    for handler in handlers {
+        handler(payload.take().unwrap_or_else(|| Box::new(message.clone())));
+    }
+    Err(None)
+}
+
+fn message(payload: &Payload) -> Option<&str> {
+    payload
+        .downcast_ref::<&'static str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
+
+/// A panic turned into an error.
+///
+/// The payload is only ever accessed by value, which keeps the error `Sync`.
+pub struct PanicError {
+    message: Option<String>,
+    payload: Mutex<Payload>,
+}
+
+impl PanicError {
+    pub fn new(payload: Payload) -> Self {
+        Self {
+            message: message(&payload).map(str::to_owned),
+            payload: Mutex::new(payload),
+        }
+    }
+
+    /// The message the panic was raised with, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The payload, e.g. to resume the panic with
+    /// [`std::panic::resume_unwind`].
+    pub fn into_payload(self) -> Payload {
+        self.payload.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PanicError").field(&self.message()).finish()
+    }
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message() {
+            Some(message) => write!(f, "panicked: {}", message),
+            None => f.write_str("panicked"),
+        }
+    }
+}
+
+impl Error for PanicError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_catch_unwind() {
+        assert!(matches!(catch_unwind(|| 1), Ok(1)));
+
+        let result = catch_unwind(|| panic!("boom"));
+        let Err(Some(payload)) = result else {
+            panic!("the panic has no handler");
+        };
+        assert_eq!(PanicError::new(payload).message(), Some("boom"));
+    }
+
+    /// Registers for the panic dropping it.
+    struct Guard(Rc<RefCell<Option<PanicError>>>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let error = self.0.clone();
+            on_unwind(move |payload| *error.borrow_mut() = Some(PanicError::new(payload)));
+        }
+    }
+
+    #[test]
+    fn test_on_unwind() {
+        let error = Rc::new(RefCell::new(None));
+        let guard = Guard(error.clone());
+        let result = catch_unwind(move || {
+            let _guard = guard;
+            panic!("boom {}", 42);
+        });
+
+        assert!(matches!(result, Err(None)));
+        let error = error.borrow_mut().take().unwrap();
+        assert_eq!(error.to_string(), "panicked: boom 42");
+    }
+
+    #[test]
+    fn test_on_unwind_caught_elsewhere() {
+        // Caught by std, the handler isn't given the next panic.
+        let error = Rc::new(RefCell::new(None));
+        let guard = Guard(error.clone());
+        let result = catch_unwind(move || {
+            let _ = panic::catch_unwind(AssertUnwindSafe(move || {
+                let _guard = guard;
+                panic!("first");
+            }));
+        });
+        assert!(result.is_ok());
+        assert!(matches!(catch_unwind(|| panic!("second")), Err(Some(_))));
+        assert!(error.borrow().is_none());
+
+        // Nothing registers without a `catch_unwind` up the stack.
+        let guard = Guard(error.clone());
+        let _ = panic::catch_unwind(AssertUnwindSafe(move || {
+            let _guard = guard;
+            panic!("third");
+        }));
+        assert!(HANDLERS.with_borrow(Vec::is_empty));
+        assert!(error.borrow().is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{Allocator, StopToken};
+
 pub trait SetValue {
     type Value;
 
@@ -13,3 +15,19 @@ pub trait SetError {
 pub trait SetStopped {
     fn set_stopped(self);
 }
+
+pub trait GetStopToken {
+    fn get_stop_token(&self) -> StopToken;
+}
+
+pub trait GetScheduler {
+    type Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler;
+}
+
+pub trait GetAllocator {
+    type Allocator: Allocator;
+
+    fn get_allocator(&self) -> Self::Allocator;
+}
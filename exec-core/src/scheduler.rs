@@ -1,7 +1,26 @@
 use crate::Sender;
+use std::time::{Duration, Instant};
 
 pub trait Scheduler<R>: Send + Clone {
     type Sender: Sender<R>;
 
     fn schedule(&mut self) -> Self::Sender;
 }
+
+/// A scheduler that can also run work at a point in time.
+///
+/// The senders of a timed schedule complete with stopped when stop is
+/// requested before their time has come.
+pub trait TimedScheduler<R>: Scheduler<R> {
+    type TimedSender: Sender<R>;
+
+    /// The current time according to the scheduler's clock.
+    fn now(&self) -> Instant;
+
+    fn schedule_at(&mut self, deadline: Instant) -> Self::TimedSender;
+
+    fn schedule_after(&mut self, delay: Duration) -> Self::TimedSender {
+        let deadline = self.now() + delay;
+        self.schedule_at(deadline)
+    }
+}
@@ -0,0 +1,76 @@
+//! Senders of several values.
+//!
+//! A [`SequenceSender`] passes each of its items to its receiver as a sender,
+//! with [`SetNext::set_next`], and gets back a sender completing once the
+//! receiver has consumed the item. The next item is only produced after that,
+//! which gives back pressure. Once out of items the sequence completes its
+//! receiver like a sender would: with `()`, an error or stopped.
+
+use crate::receiver::{GetStopToken, SetStopped, SetValue};
+use crate::{OperationState, Sender, StopToken};
+use std::ptr::NonNull;
+
+pub trait SequenceSender<R> {
+    type Error;
+
+    type Operation: OperationState;
+
+    fn subscribe(self, receiver: R) -> Self::Operation;
+}
+
+pub trait SetNext<S> {
+    /// Completes with `()` once the item has been consumed, or stopped if the
+    /// receiver doesn't want any more items.
+    type Next: Sender<NextReceiver>;
+
+    fn set_next(&mut self, item: S) -> Self::Next;
+}
+
+/// The receiver sequences connect the senders returned by
+/// [`SetNext::set_next`] to.
+pub struct NextReceiver {
+    op: NonNull<()>,
+    complete: unsafe fn(NonNull<()>, bool),
+    stop_token: StopToken,
+}
+
+// The sequence operation outlives its pending next operation.
+unsafe impl Send for NextReceiver {}
+
+impl NextReceiver {
+    /// # Safety
+    ///
+    /// `op` must stay valid until the receiver calls `complete` with it and
+    /// whether more items are wanted.
+    pub unsafe fn new(
+        op: NonNull<()>,
+        complete: unsafe fn(NonNull<()>, bool),
+        stop_token: StopToken,
+    ) -> Self {
+        Self {
+            op,
+            complete,
+            stop_token,
+        }
+    }
+}
+
+impl SetValue for NextReceiver {
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        unsafe { (self.complete)(self.op, true) }
+    }
+}
+
+impl SetStopped for NextReceiver {
+    fn set_stopped(self) {
+        unsafe { (self.complete)(self.op, false) }
+    }
+}
+
+impl GetStopToken for NextReceiver {
+    fn get_stop_token(&self) -> StopToken {
+        self.stop_token.clone()
+    }
+}
@@ -0,0 +1,213 @@
+//! Cooperative cancellation.
+//!
+//! A [`StopSource`] owns the stop state, the [`StopToken`]s handed out to
+//! operations can be polled with [`StopToken::stop_requested`] or observed
+//! by registering a [`StopCallback`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, ThreadId};
+
+type Callback = Box<dyn FnOnce() + Send>;
+
+struct State {
+    stopped: AtomicBool,
+    inner: Mutex<Inner>,
+    cv: Condvar,
+}
+
+struct Inner {
+    next_id: u64,
+    callbacks: Vec<(u64, Callback)>,
+    /// The callback being invoked by `request_stop`, and on which thread.
+    running: Option<(u64, ThreadId)>,
+}
+
+/// Owner of a stop state, requests stop on every associated token.
+///
+/// Clones share the same stop state.
+#[derive(Clone)]
+pub struct StopSource {
+    state: Arc<State>,
+}
+
+impl StopSource {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(State {
+                stopped: AtomicBool::new(false),
+                inner: Mutex::new(Inner {
+                    next_id: 0,
+                    callbacks: Vec::new(),
+                    running: None,
+                }),
+                cv: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn token(&self) -> StopToken {
+        StopToken {
+            state: Some(self.state.clone()),
+        }
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        self.state.stopped.load(Ordering::Acquire)
+    }
+
+    /// Requests stop and invokes the registered callbacks on the calling
+    /// thread. Returns false if stop had already been requested.
+    pub fn request_stop(&self) -> bool {
+        if self.state.stopped.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+
+        let mut inner = self.state.inner.lock().unwrap();
+        while let Some((id, callback)) = inner.callbacks.pop() {
+            inner.running = Some((id, thread::current().id()));
+            drop(inner);
+            callback();
+            inner = self.state.inner.lock().unwrap();
+            inner.running = None;
+            self.state.cv.notify_all();
+        }
+        true
+    }
+}
+
+impl Default for StopSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle to observe stop requests of a [`StopSource`].
+#[derive(Clone, Default)]
+pub struct StopToken {
+    state: Option<Arc<State>>,
+}
+
+impl StopToken {
+    /// A token that is never stopped.
+    pub fn never() -> Self {
+        Self { state: None }
+    }
+
+    pub fn stop_requested(&self) -> bool {
+        self.state
+            .as_ref()
+            .is_some_and(|state| state.stopped.load(Ordering::Acquire))
+    }
+
+    pub fn stop_possible(&self) -> bool {
+        self.state.is_some()
+    }
+}
+
+/// A callback invoked once stop is requested on a token.
+///
+/// The callback runs inline on construction if stop was already requested.
+/// Dropping the `StopCallback` deregisters it, if it is being invoked on
+/// another thread at that moment, the drop waits for it to return.
+pub struct StopCallback {
+    state: Option<Arc<State>>,
+    id: u64,
+}
+
+impl StopCallback {
+    pub fn new<F>(token: &StopToken, callback: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let Some(state) = &token.state else {
+            return Self { state: None, id: 0 };
+        };
+
+        let mut inner = state.inner.lock().unwrap();
+        if state.stopped.load(Ordering::Acquire) {
+            drop(inner);
+            callback();
+            return Self { state: None, id: 0 };
+        }
+
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.callbacks.push((id, Box::new(callback)));
+        Self {
+            state: Some(state.clone()),
+            id,
+        }
+    }
+}
+
+impl Drop for StopCallback {
+    fn drop(&mut self) {
+        let Some(state) = self.state.take() else {
+            return;
+        };
+
+        let mut inner = state.inner.lock().unwrap();
+        if let Some(index) = inner.callbacks.iter().position(|(id, _)| *id == self.id) {
+            let callback = inner.callbacks.swap_remove(index);
+            drop(inner);
+            drop(callback);
+            return;
+        }
+
+        // Already invoked or being invoked. A callback dropping itself must
+        // not wait for itself to return.
+        let current = thread::current().id();
+        while matches!(inner.running, Some((id, thread)) if id == self.id && thread != current) {
+            inner = state.cv.wait(inner).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_request_stop() {
+        let source = StopSource::new();
+        let token = source.token();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let _callback = {
+            let count = count.clone();
+            StopCallback::new(&token, move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let removed = {
+            let count = count.clone();
+            StopCallback::new(&token, move || {
+                count.fetch_add(10, Ordering::SeqCst);
+            })
+        };
+        drop(removed);
+
+        assert!(!token.stop_requested());
+        assert!(source.request_stop());
+        assert!(!source.request_stop());
+        assert!(token.stop_requested());
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // Registering after the fact runs the callback inline.
+        let count_inline = count.clone();
+        let _late = StopCallback::new(&token, move || {
+            count_inline.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_never() {
+        let token = StopToken::never();
+        assert!(!token.stop_possible());
+        let _callback = StopCallback::new(&token, || unreachable!());
+        assert!(!token.stop_requested());
+    }
+}
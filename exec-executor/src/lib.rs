@@ -2,9 +2,17 @@
 mod macros;
 
 mod run_loop;
-pub use run_loop::RunLoop;
+pub use run_loop::{PanicPolicy, QueueKind, RunLoop, RunLoopScheduler};
 
 mod single_thread_context;
 pub use single_thread_context::SingleThreadContext;
 
+mod trampoline;
+pub use trampoline::{ScheduleTrampoline, TrampolineOperation, TrampolineScheduler};
+
+#[cfg(feature = "tokio")]
+mod tokio_scheduler;
+#[cfg(feature = "tokio")]
+pub use tokio_scheduler::TokioScheduler;
+
 mod utils;
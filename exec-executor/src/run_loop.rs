@@ -1,23 +1,49 @@
 use crate::utils::linked_list::{self, LinkedList};
-use exec_core::receiver::SetValue;
-use exec_core::{OperationState, Scheduler, Sender};
+use crate::utils::mpsc_queue::{self, MpscQueue, Pop};
+use crate::utils::parker::Parker;
+use exec_core::panic::{self, PanicError};
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::{OperationState, Scheduler, Sender, StopCallback, TimedScheduler};
+use scopeguard::defer;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::error::Error;
 use std::marker::{PhantomData, PhantomPinned};
-use std::ptr::NonNull;
-use std::sync::{Condvar, Mutex};
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
 
 type TaskQueue = LinkedList<Task, <Task as linked_list::Link>::Target>;
 
 struct Task {
     pointers: linked_list::Pointers<Task>,
-    execute: fn(*mut Task),
+    next: AtomicPtr<Task>,
+    execute: fn(*mut Task, stopped: bool),
     _p: PhantomPinned,
 }
 
+impl Task {
+    fn new(execute: fn(*mut Task, stopped: bool)) -> Self {
+        Self {
+            pointers: linked_list::Pointers::new(),
+            next: AtomicPtr::new(ptr::null_mut()),
+            execute,
+            _p: PhantomPinned,
+        }
+    }
+}
+
 generate_addr_of_methods! {
     impl<> Task<> {
         unsafe fn addr_of_pointers(self: NonNull<Self>) -> NonNull<linked_list::Pointers<Task>> {
             &self.pointers
         }
+
+        unsafe fn addr_of_next(self: NonNull<Self>) -> NonNull<AtomicPtr<Task>> {
+            &self.next
+        }
     }
 }
 
@@ -25,30 +51,157 @@ generate_addr_of_methods! {
 pub struct Operation<R> {
     base: Task,
     receiver: Option<R>,
-    run_loop: NonNull<RunLoop>,
+    run_loop: Weak<Shared>,
 }
 
 impl<R> Operation<R>
 where
-    R: SetValue<Value = ()>,
+    R: SetValue<Value = ()> + SetStopped,
 {
-    fn execute(task: *mut Task) {
+    fn execute(task: *mut Task, stopped: bool) {
         let operation = unsafe { &mut *(task as *mut Operation<R>) };
         if let Some(receiver) = operation.receiver.take() {
-            receiver.set_value(());
+            if stopped {
+                receiver.set_stopped();
+            } else {
+                receiver.set_value(());
+            }
         }
     }
 }
 
-impl<R> OperationState for Operation<R> {
+impl<R> OperationState for Operation<R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
     fn start(&mut self) {
-        unsafe {
-            self.run_loop.as_mut().push_front(NonNull::from(&self.base));
+        // The run loop is gone, nobody would ever execute the task.
+        match self.run_loop.upgrade() {
+            Some(run_loop) => run_loop.push_front(NonNull::from(&self.base)),
+            None => Self::execute(&mut self.base, true),
         }
     }
 }
 
+#[repr(C)]
+pub struct TimerOperation<R> {
+    base: Task,
+    receiver: Option<R>,
+    run_loop: Weak<Shared>,
+    deadline: Instant,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<R> TimerOperation<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn execute(task: *mut Task, stopped: bool) {
+        let operation = unsafe { &mut *(task as *mut TimerOperation<R>) };
+        operation.stop_callback.take();
+        if let Some(receiver) = operation.receiver.take() {
+            // Stop may have been requested while the timer was firing.
+            if stopped || receiver.get_stop_token().stop_requested() {
+                receiver.set_stopped();
+            } else {
+                receiver.set_value(());
+            }
+        }
+    }
+}
+
+impl<R> OperationState for TimerOperation<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn start(&mut self) {
+        let Some(run_loop) = self.run_loop.upgrade() else {
+            return Self::execute(&mut self.base, true);
+        };
+
+        let key = run_loop.timer_key(self.deadline);
+        let token = self.receiver.as_ref().unwrap().get_stop_token();
+        if token.stop_possible() {
+            let run_loop = self.run_loop.clone();
+            self.stop_callback = Some(StopCallback::new(&token, move || {
+                if let Some(run_loop) = run_loop.upgrade() {
+                    run_loop.cancel_timer(key);
+                }
+            }));
+        }
+
+        if !run_loop.add_timer(key, NonNull::from(&self.base)) {
+            Self::execute(&mut self.base, true);
+        }
+    }
+}
+
+/// What a [`RunLoop`] does when a task panics.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// The panic unwinds out of the call driving the loop.
+    #[default]
+    Propagate,
+    /// The panic is caught and delivered to the receivers it unwound through,
+    /// see [`exec_core::panic`]. A panic nobody takes over is reported to the
+    /// unhandled-error hook as a [`PanicError`]. The loop keeps running.
+    Contain,
+}
+
+/// The kind of queue backing a [`RunLoop`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum QueueKind {
+    /// A linked list guarded by a mutex, woken up through a condition variable
+    /// on every push. Any number of threads may drive the loop concurrently.
+    #[default]
+    Mutex,
+    /// An intrusive lock-free MPSC queue. Producers never take a lock, the
+    /// consumer only sleeps when it runs out of work. The loop must be driven
+    /// by a single thread at a time.
+    LockFree,
+}
+
+/// A FIFO queue of work driven by the thread calling [`RunLoop::run`].
+///
+/// Timed work is queued once its deadline has passed, by the thread driving
+/// the loop. [`RunLoop::run`] doesn't wait for timers that haven't fired yet.
+///
+/// Schedulers only keep a weak reference to the loop. Once the `RunLoop` is
+/// dropped, work that is still queued and work scheduled afterwards completes
+/// with stopped instead of running.
 pub struct RunLoop {
+    shared: Arc<Shared>,
+}
+
+type ErrorHook = dyn Fn(&dyn Error) + Send + Sync;
+
+struct Shared {
+    queue: Queue,
+    timers: Mutex<Timers>,
+    error_hook: Mutex<Option<Arc<ErrorHook>>>,
+    contain_panics: AtomicBool,
+}
+
+/// Pending timers by deadline, then by order of scheduling.
+type TimerKey = (Instant, u64);
+
+struct Timers {
+    /// `None` marks a timer cancelled before it has been added.
+    queue: BTreeMap<TimerKey, Option<TimerTask>>,
+    next_id: u64,
+}
+
+struct TimerTask(NonNull<Task>);
+
+// The task is only executed once, by whoever removes it from `Timers`.
+unsafe impl Send for TimerTask {}
+
+enum Queue {
+    Mutex(LockedQueue),
+    LockFree(LockFreeQueue),
+}
+
+struct LockedQueue {
     inner: Mutex<Inner>,
     cv: Condvar,
 }
@@ -58,86 +211,423 @@ struct Inner {
     stop: bool,
 }
 
+struct LockFreeQueue {
+    queue: MpscQueue<Task>,
+    parker: Parker,
+    stop: AtomicBool,
+    running: AtomicBool,
+}
+
 impl RunLoop {
     pub fn new() -> Self {
+        Self::with_queue(QueueKind::default())
+    }
+
+    pub fn with_queue(kind: QueueKind) -> Self {
+        let queue = match kind {
+            QueueKind::Mutex => Queue::Mutex(LockedQueue {
+                inner: Mutex::new(Inner {
+                    queue: TaskQueue::new(),
+                    stop: false,
+                }),
+                cv: Condvar::new(),
+            }),
+            QueueKind::LockFree => Queue::LockFree(LockFreeQueue {
+                queue: MpscQueue::new(Box::new(Task::new(|_, _| {}))),
+                parker: Parker::new(),
+                stop: AtomicBool::new(false),
+                running: AtomicBool::new(false),
+            }),
+        };
         Self {
-            inner: Mutex::new(Inner {
-                queue: TaskQueue::new(),
-                stop: false,
+            shared: Arc::new(Shared {
+                queue,
+                timers: Mutex::new(Timers {
+                    queue: BTreeMap::new(),
+                    next_id: 0,
+                }),
+                error_hook: Mutex::new(None),
+                contain_panics: AtomicBool::new(false),
             }),
-            cv: Condvar::new(),
         }
     }
 
-    fn push_front(&self, task: <Task as linked_list::Link>::Handle) {
-        let mut inner = self.inner.lock().unwrap();
-        inner.queue.push_front(task);
-        self.cv.notify_one();
-    }
-
-    fn pop_back(&self) -> Option<<Task as linked_list::Link>::Handle> {
-        let mut inner = self.inner.lock().unwrap();
-        loop {
-            let item = inner.queue.pop_back();
-            if inner.stop || item.is_some() {
-                break item;
-            } else {
-                inner = self.cv.wait(inner).unwrap();
-            }
+    pub fn queue_kind(&self) -> QueueKind {
+        match self.shared.queue {
+            Queue::Mutex(_) => QueueKind::Mutex,
+            Queue::LockFree(_) => QueueKind::LockFree,
         }
     }
 
     pub fn finish(&self) {
-        let mut inner = self.inner.lock().unwrap();
-        inner.stop = true;
-        self.cv.notify_all();
+        self.shared.finish();
     }
 
+    /// Drains the queue until [`RunLoop::finish`] has been called and no work
+    /// is left.
     pub fn run(&self) {
-        while let Some(mut task) = self.pop_back() {
+        self.drive(Until::Finished);
+    }
+
+    /// Drains the queue until `done` returns true, sleeping while there is no
+    /// work. Whoever makes `done` true must call [`RunLoop::notify`] afterwards.
+    ///
+    /// This may be called from a task running on this very loop, in which
+    /// case the nested call keeps executing the queued work.
+    pub fn run_until(&self, done: impl Fn() -> bool) {
+        self.drive(Until::Done(&done));
+    }
+
+    /// Like [`RunLoop::run_until`], but gives up once `deadline` has passed.
+    /// Returns whether `done` is satisfied.
+    pub fn run_until_deadline(&self, done: impl Fn() -> bool, deadline: Instant) -> bool {
+        self.drive(Until::Deadline(&done, deadline));
+        done()
+    }
+
+    /// Wakes up the threads sleeping in [`RunLoop::run_until`] so that they
+    /// check their condition again.
+    pub fn notify(&self) {
+        self.shared.notify();
+    }
+
+    /// Returns a handle to the run loop driven by the current thread, if any.
+    pub fn current() -> Option<RunLoop> {
+        CURRENT.with(|current| {
+            let shared = current.get();
+            if shared.is_null() {
+                return None;
+            }
+            // SAFETY: the pointer is only set while `drive` borrows a live
+            // `Arc<Shared>` on this thread.
             unsafe {
-                (task.as_mut().execute)(task.as_ptr());
+                Arc::increment_strong_count(shared);
+                Some(RunLoop {
+                    shared: Arc::from_raw(shared),
+                })
             }
-        }
+        })
     }
 
     pub fn get_scheduler(&self) -> RunLoopScheduler {
         RunLoopScheduler {
-            run_loop: NonNull::from(self),
+            run_loop: Arc::downgrade(&self.shared),
+        }
+    }
+
+    pub fn set_panic_policy(&self, policy: PanicPolicy) {
+        let contain = policy == PanicPolicy::Contain;
+        self.shared.contain_panics.store(contain, Ordering::Relaxed);
+    }
+
+    pub fn panic_policy(&self) -> PanicPolicy {
+        match self.shared.contain_panics.load(Ordering::Relaxed) {
+            true => PanicPolicy::Contain,
+            false => PanicPolicy::Propagate,
+        }
+    }
+
+    /// Sets the hook receiving the errors nobody handles in work running on
+    /// this loop, e.g. the errors of detached operations.
+    pub fn set_unhandled_error_hook<F>(&self, hook: F)
+    where
+        F: Fn(&dyn Error) + Send + Sync + 'static,
+    {
+        *self.shared.error_hook.lock().unwrap() = Some(Arc::new(hook));
+    }
+
+    /// Passes `error` to the unhandled-error hook. Returns false if no hook is
+    /// set.
+    pub fn report_unhandled_error(&self, error: &dyn Error) -> bool {
+        let hook = self.shared.error_hook.lock().unwrap().clone();
+        match hook {
+            Some(hook) => {
+                hook(error);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn drive(&self, until: Until<'_>) {
+        let shared = Arc::as_ptr(&self.shared);
+        let previous = CURRENT.with(|current| current.replace(shared));
+        defer! {
+            CURRENT.with(|current| current.set(previous));
+        }
+
+        // A nested call on the same thread is still the same consumer.
+        let _consumer = match &self.shared.queue {
+            Queue::LockFree(queue) if previous != shared => {
+                assert!(
+                    !queue.running.swap(true, Ordering::Acquire),
+                    "a lock-free RunLoop must be driven by a single thread"
+                );
+                Some(scopeguard::guard(&queue.running, |running| {
+                    running.store(false, Ordering::Release)
+                }))
+            }
+            _ => None,
+        };
+
+        while let Some(task) = self.shared.pop_back(&until) {
+            let execute = || unsafe { (task.as_ref().execute)(task.as_ptr(), false) };
+            if self.panic_policy() == PanicPolicy::Propagate {
+                execute();
+            } else if let Err(Some(payload)) = panic::catch_unwind(execute) {
+                self.report_unhandled_error(&PanicError::new(payload));
+            }
+        }
+    }
+}
+
+impl Clone for RunLoop {
+    /// Returns another handle to the same run loop.
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Default for RunLoop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    /// The run loop being driven by this thread.
+    static CURRENT: Cell<*const Shared> = const { Cell::new(ptr::null()) };
+}
+
+/// When a thread driving the loop gives up on an empty queue.
+enum Until<'a> {
+    Finished,
+    Done(&'a dyn Fn() -> bool),
+    Deadline(&'a dyn Fn() -> bool, Instant),
+}
+
+impl Until<'_> {
+    /// Whether the caller's condition is met, regardless of queued work.
+    fn is_done(&self) -> bool {
+        match self {
+            Until::Finished => false,
+            Until::Done(done) => done(),
+            Until::Deadline(done, deadline) => done() || Instant::now() >= *deadline,
+        }
+    }
+
+    /// How long to sleep at most while waiting for work, or for the next
+    /// timer to fire.
+    fn timeout(&self, next_timer: Option<Instant>) -> Option<Duration> {
+        let deadline = match self {
+            Until::Deadline(_, deadline) => Some(*deadline),
+            _ => None,
+        };
+        let deadline = match (deadline, next_timer) {
+            (Some(deadline), Some(next_timer)) => Some(deadline.min(next_timer)),
+            (deadline, next_timer) => deadline.or(next_timer),
+        };
+        deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+impl Shared {
+    fn push_front(&self, task: NonNull<Task>) {
+        match &self.queue {
+            Queue::Mutex(queue) => {
+                let mut inner = queue.inner.lock().unwrap();
+                inner.queue.push_front(task);
+                queue.cv.notify_one();
+            }
+            Queue::LockFree(queue) => {
+                unsafe { queue.queue.push(task) };
+                queue.parker.unpark();
+            }
+        }
+    }
+
+    fn pop_back(&self, until: &Until<'_>) -> Option<NonNull<Task>> {
+        match &self.queue {
+            Queue::Mutex(queue) => {
+                let mut inner = queue.inner.lock().unwrap();
+                loop {
+                    if until.is_done() {
+                        break None;
+                    }
+                    let next_timer = self.fire_timers(|task| inner.queue.push_front(task));
+                    let item = inner.queue.pop_back();
+                    if item.is_some() || matches!(until, Until::Finished if inner.stop) {
+                        break item;
+                    } else {
+                        inner = match until.timeout(next_timer) {
+                            Some(timeout) => queue.cv.wait_timeout(inner, timeout).unwrap().0,
+                            None => queue.cv.wait(inner).unwrap(),
+                        };
+                    }
+                }
+            }
+            Queue::LockFree(queue) => loop {
+                if until.is_done() {
+                    break None;
+                }
+                let next_timer = self.fire_timers(|task| unsafe { queue.queue.push(task) });
+                // Only the thread driving the loop pops, see `drive`.
+                match unsafe { queue.queue.pop() } {
+                    Pop::Data(task) => break Some(task),
+                    Pop::Inconsistent => thread::yield_now(),
+                    Pop::Empty => {
+                        if matches!(until, Until::Finished if queue.stop.load(Ordering::Acquire)) {
+                            break None;
+                        }
+                        match until.timeout(next_timer) {
+                            Some(timeout) => queue.parker.park_timeout(timeout),
+                            None => queue.parker.park(),
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    fn timer_key(&self, deadline: Instant) -> TimerKey {
+        let mut timers = self.timers.lock().unwrap();
+        timers.next_id += 1;
+        (deadline, timers.next_id)
+    }
+
+    /// Returns false if the timer has already been cancelled.
+    fn add_timer(&self, key: TimerKey, task: NonNull<Task>) -> bool {
+        let mut timers = self.timers.lock().unwrap();
+        if timers.queue.remove(&key).is_some() {
+            return false;
+        }
+        timers.queue.insert(key, Some(TimerTask(task)));
+        drop(timers);
+        // The deadline may be earlier than the one the loop sleeps until.
+        self.notify();
+        true
+    }
+
+    /// Completes the timer with stopped, right away if it is pending.
+    fn cancel_timer(&self, key: TimerKey) {
+        let mut timers = self.timers.lock().unwrap();
+        match timers.queue.remove(&key) {
+            Some(Some(task)) => {
+                drop(timers);
+                unsafe { (task.0.as_ref().execute)(task.0.as_ptr(), true) };
+            }
+            Some(None) => {}
+            // Not added yet, or already fired, in which case the marker is
+            // dropped with the other expired timers.
+            None => {
+                timers.queue.insert(key, None);
+            }
+        }
+    }
+
+    /// Passes the expired timers to `fire`, returns the deadline of the next
+    /// one.
+    fn fire_timers(&self, mut fire: impl FnMut(NonNull<Task>)) -> Option<Instant> {
+        let mut timers = self.timers.lock().unwrap();
+        let now = Instant::now();
+        while let Some(entry) = timers.queue.first_entry() {
+            if entry.key().0 > now {
+                return Some(entry.key().0);
+            }
+            if let Some(task) = entry.remove() {
+                fire(task.0);
+            }
+        }
+        None
+    }
+
+    fn finish(&self) {
+        match &self.queue {
+            Queue::Mutex(queue) => {
+                let mut inner = queue.inner.lock().unwrap();
+                inner.stop = true;
+                queue.cv.notify_all();
+            }
+            Queue::LockFree(queue) => {
+                queue.stop.store(true, Ordering::Release);
+                queue.parker.unpark();
+            }
+        }
+    }
+
+    fn notify(&self) {
+        match &self.queue {
+            Queue::Mutex(queue) => {
+                let _inner = queue.inner.lock().unwrap();
+                queue.cv.notify_all();
+            }
+            Queue::LockFree(queue) => queue.parker.unpark(),
         }
     }
 }
 
-#[derive(Copy, Clone)]
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // Schedulers can no longer reach the queue, stop whatever is left.
+        let mut pop_back = || match &mut self.queue {
+            Queue::Mutex(queue) => queue.inner.get_mut().unwrap().queue.pop_back(),
+            Queue::LockFree(queue) => loop {
+                match unsafe { queue.queue.pop() } {
+                    Pop::Data(task) => break Some(task),
+                    Pop::Inconsistent => thread::yield_now(),
+                    Pop::Empty => break None,
+                }
+            },
+        };
+
+        while let Some(mut task) = pop_back() {
+            unsafe {
+                (task.as_mut().execute)(task.as_ptr(), true);
+            }
+        }
+
+        let timers = std::mem::take(&mut self.timers.get_mut().unwrap().queue);
+        for task in timers.into_values().flatten() {
+            unsafe {
+                (task.0.as_ref().execute)(task.0.as_ptr(), true);
+            }
+        }
+    }
+}
+
+/// Handle to schedule work on a [`RunLoop`].
+///
+/// Scheduling on a dropped run loop completes with stopped.
+#[derive(Clone)]
 pub struct RunLoopScheduler {
-    run_loop: NonNull<RunLoop>,
+    run_loop: Weak<Shared>,
 }
 
 impl<R> Scheduler<R> for RunLoopScheduler
 where
-    R: SetValue<Value = ()>,
+    R: SetValue<Value = ()> + SetStopped,
 {
     type Sender = ScheduleTask<R>;
 
     fn schedule(&mut self) -> Self::Sender {
         ScheduleTask {
-            run_loop: self.run_loop,
+            run_loop: self.run_loop.clone(),
             _marker: PhantomData,
         }
     }
 }
 
-unsafe impl Send for RunLoopScheduler {}
-
 /// Sender to schedule task in run loop.
 pub struct ScheduleTask<R> {
-    run_loop: NonNull<RunLoop>,
+    run_loop: Weak<Shared>,
     _marker: PhantomData<R>,
 }
 
 impl<R> Sender<R> for ScheduleTask<R>
 where
-    R: SetValue<Value = ()>,
+    R: SetValue<Value = ()> + SetStopped,
 {
     type Value = R::Value;
     type Error = ();
@@ -146,17 +636,59 @@ where
 
     fn connect(self, receiver: R) -> Self::Operation {
         Operation {
-            base: Task {
-                pointers: linked_list::Pointers::new(),
-                execute: Operation::<R>::execute,
-                _p: PhantomPinned,
-            },
+            base: Task::new(Operation::<R>::execute),
             receiver: Some(receiver),
             run_loop: self.run_loop,
         }
     }
 }
 
+impl<R> TimedScheduler<R> for RunLoopScheduler
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type TimedSender = ScheduleAt<R>;
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn schedule_at(&mut self, deadline: Instant) -> Self::TimedSender {
+        ScheduleAt {
+            run_loop: self.run_loop.clone(),
+            deadline,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Sender to schedule task in run loop once `deadline` has passed.
+pub struct ScheduleAt<R> {
+    run_loop: Weak<Shared>,
+    deadline: Instant,
+    _marker: PhantomData<R>,
+}
+
+impl<R> Sender<R> for ScheduleAt<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Value = R::Value;
+    type Error = ();
+
+    type Operation = TimerOperation<R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        TimerOperation {
+            base: Task::new(TimerOperation::<R>::execute),
+            receiver: Some(receiver),
+            run_loop: self.run_loop,
+            deadline: self.deadline,
+            stop_callback: None,
+        }
+    }
+}
+
 unsafe impl linked_list::Link for Task {
     type Handle = NonNull<Task>;
     type Target = Task;
@@ -174,10 +706,21 @@ unsafe impl linked_list::Link for Task {
     }
 }
 
+unsafe impl mpsc_queue::Link for Task {
+    type Target = Task;
+
+    unsafe fn next(target: NonNull<Task>) -> NonNull<AtomicPtr<Task>> {
+        Task::addr_of_next(target)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use exec_test::receivers::ExpectValueReceiver;
+    use exec_core::{StopSource, StopToken};
+    use exec_test::receivers::{ExpectStoppedReceiver, ExpectValueReceiver};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
 
     #[test]
     fn test_run_loop() {
@@ -191,4 +734,195 @@ mod tests {
         // Drain the works in run loop
         run_loop.run();
     }
+
+    struct CountingReceiver {
+        count: Arc<AtomicUsize>,
+        total: usize,
+        run_loop: Arc<RunLoop>,
+    }
+
+    impl SetValue for CountingReceiver {
+        type Value = ();
+
+        fn set_value(self, _value: Self::Value) {
+            if self.count.fetch_add(1, Ordering::SeqCst) + 1 == self.total {
+                self.run_loop.finish();
+            }
+        }
+    }
+
+    impl SetStopped for CountingReceiver {
+        fn set_stopped(self) {
+            panic!("task unexpectedly stopped");
+        }
+    }
+
+    #[test]
+    fn test_lock_free_run_loop() {
+        const PRODUCERS: usize = 4;
+        const TASKS: usize = 500;
+
+        let run_loop = Arc::new(RunLoop::with_queue(QueueKind::LockFree));
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|_| {
+                let run_loop = run_loop.clone();
+                let count = count.clone();
+                thread::spawn(move || {
+                    let mut scheduler = run_loop.get_scheduler();
+                    let mut ops: Vec<_> = (0..TASKS)
+                        .map(|_| {
+                            scheduler.schedule().connect(CountingReceiver {
+                                count: count.clone(),
+                                total: PRODUCERS * TASKS,
+                                run_loop: run_loop.clone(),
+                            })
+                        })
+                        .collect();
+                    ops.iter_mut().for_each(|op| op.start());
+                    // Keep the operations alive until the loop has run them.
+                    while count.load(Ordering::SeqCst) < PRODUCERS * TASKS {
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+
+        run_loop.run();
+        assert_eq!(count.load(Ordering::SeqCst), PRODUCERS * TASKS);
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_schedule_on_dropped_run_loop() {
+        let run_loop = RunLoop::new();
+        let mut scheduler = run_loop.get_scheduler();
+
+        // Queued work is stopped when the loop goes away without running it.
+        let mut queued = scheduler.schedule().connect(ExpectStoppedReceiver::new());
+        queued.start();
+        drop(run_loop);
+
+        // Scheduling afterwards is stopped right away.
+        let mut op = scheduler.schedule().connect(ExpectStoppedReceiver::new());
+        op.start();
+    }
+
+    struct PanickingReceiver;
+
+    impl SetValue for PanickingReceiver {
+        type Value = ();
+
+        fn set_value(self, _value: Self::Value) {
+            panic!("boom");
+        }
+    }
+
+    impl SetStopped for PanickingReceiver {
+        fn set_stopped(self) {}
+    }
+
+    #[test]
+    fn test_contain_panics() {
+        let run_loop = RunLoop::new();
+        run_loop.set_panic_policy(PanicPolicy::Contain);
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        {
+            let errors = errors.clone();
+            run_loop.set_unhandled_error_hook(move |error| {
+                errors.lock().unwrap().push(error.to_string());
+            });
+        }
+
+        let mut scheduler = run_loop.get_scheduler();
+        let mut panicking = scheduler.schedule().connect(PanickingReceiver);
+        let mut op = scheduler.schedule().connect(ExpectValueReceiver::new(()));
+        panicking.start();
+        op.start();
+        run_loop.finish();
+        run_loop.run();
+
+        assert_eq!(*errors.lock().unwrap(), ["panicked: boom"]);
+    }
+
+    struct TimerReceiver {
+        id: u32,
+        fired: Arc<Mutex<Vec<(u32, bool)>>>,
+        stop_token: StopToken,
+    }
+
+    impl SetValue for TimerReceiver {
+        type Value = ();
+
+        fn set_value(self, _value: Self::Value) {
+            self.fired.lock().unwrap().push((self.id, true));
+        }
+    }
+
+    impl SetStopped for TimerReceiver {
+        fn set_stopped(self) {
+            self.fired.lock().unwrap().push((self.id, false));
+        }
+    }
+
+    impl GetStopToken for TimerReceiver {
+        fn get_stop_token(&self) -> StopToken {
+            self.stop_token.clone()
+        }
+    }
+
+    #[test]
+    fn test_timers() {
+        for kind in [QueueKind::Mutex, QueueKind::LockFree] {
+            let run_loop = RunLoop::with_queue(kind);
+            let mut scheduler = run_loop.get_scheduler();
+            let fired = Arc::new(Mutex::new(Vec::new()));
+            let stop_source = StopSource::new();
+            let receiver = |id, stop_token| TimerReceiver {
+                id,
+                fired: fired.clone(),
+                stop_token,
+            };
+
+            let start = Instant::now();
+            let mut late = scheduler
+                .schedule_after(Duration::from_millis(20))
+                .connect(receiver(1, StopToken::never()));
+            let mut early = scheduler
+                .schedule_at(start)
+                .connect(receiver(2, StopToken::never()));
+            let mut stopped = scheduler
+                .schedule_after(Duration::from_secs(60))
+                .connect(receiver(3, stop_source.token()));
+            late.start();
+            early.start();
+            stopped.start();
+
+            stop_source.request_stop();
+            run_loop.run_until(|| fired.lock().unwrap().len() == 3);
+            assert_eq!(*fired.lock().unwrap(), [(3, false), (2, true), (1, true)]);
+            assert!(start.elapsed() >= Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_timers_on_dropped_run_loop() {
+        let run_loop = RunLoop::new();
+        let mut scheduler = run_loop.get_scheduler();
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let mut op = scheduler
+            .schedule_after(Duration::from_secs(60))
+            .connect(TimerReceiver {
+                id: 1,
+                fired: fired.clone(),
+                stop_token: StopToken::never(),
+            });
+        op.start();
+        drop(run_loop);
+        assert_eq!(*fired.lock().unwrap(), [(1, false)]);
+    }
 }
@@ -1,5 +1,6 @@
 use crate::run_loop::RunLoopScheduler;
-use crate::RunLoop;
+use crate::{PanicPolicy, QueueKind, RunLoop};
+use std::error::Error;
 use std::sync::Arc;
 use std::thread;
 
@@ -10,7 +11,14 @@ pub struct SingleThreadContext {
 
 impl SingleThreadContext {
     pub fn new() -> Self {
-        let run_loop = Arc::new(RunLoop::new());
+        Self::with_queue(QueueKind::default())
+    }
+
+    /// A panicking task doesn't bring the thread down, see
+    /// [`PanicPolicy::Contain`].
+    pub fn with_queue(kind: QueueKind) -> Self {
+        let run_loop = Arc::new(RunLoop::with_queue(kind));
+        run_loop.set_panic_policy(PanicPolicy::Contain);
         let thread;
         {
             let run_loop = run_loop.clone();
@@ -27,6 +35,19 @@ impl SingleThreadContext {
     pub fn get_scheduler(&self) -> RunLoopScheduler {
         self.run_loop.get_scheduler()
     }
+
+    /// See [`RunLoop::set_panic_policy`].
+    pub fn set_panic_policy(&self, policy: PanicPolicy) {
+        self.run_loop.set_panic_policy(policy);
+    }
+
+    /// See [`RunLoop::set_unhandled_error_hook`].
+    pub fn set_unhandled_error_hook<F>(&self, hook: F)
+    where
+        F: Fn(&dyn Error) + Send + Sync + 'static,
+    {
+        self.run_loop.set_unhandled_error_hook(hook);
+    }
 }
 
 impl Default for SingleThreadContext {
@@ -41,3 +62,20 @@ impl Drop for SingleThreadContext {
         self.thread.take().unwrap().join().unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exec_core::{OperationState, Scheduler, Sender};
+    use exec_test::receivers::ExpectStoppedReceiver;
+
+    #[test]
+    fn test_scheduler_outlives_context() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+        drop(context);
+
+        let mut op = scheduler.schedule().connect(ExpectStoppedReceiver::new());
+        op.start();
+    }
+}
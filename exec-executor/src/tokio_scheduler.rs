@@ -0,0 +1,155 @@
+use exec_core::receiver::{SetStopped, SetValue};
+use exec_core::{OperationState, Scheduler, Sender};
+use std::marker::PhantomData;
+use tokio::runtime::Handle;
+
+/// Handle to schedule work on a Tokio runtime.
+///
+/// Each scheduled operation is spawned as a Tokio task. Scheduling on a
+/// runtime that has shut down completes with stopped.
+#[derive(Clone)]
+pub struct TokioScheduler {
+    handle: Handle,
+}
+
+impl TokioScheduler {
+    pub fn new(handle: Handle) -> Self {
+        Self { handle }
+    }
+
+    /// Scheduler of the runtime the caller runs on.
+    ///
+    /// Panics when called outside of a Tokio runtime.
+    pub fn current() -> Self {
+        Self::new(Handle::current())
+    }
+
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+}
+
+impl<R> Scheduler<R> for TokioScheduler
+where
+    R: SetValue<Value = ()> + SetStopped + Send + 'static,
+{
+    type Sender = ScheduleTokio<R>;
+
+    fn schedule(&mut self) -> Self::Sender {
+        ScheduleTokio {
+            handle: self.handle.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Sender to schedule task on a Tokio runtime.
+pub struct ScheduleTokio<R> {
+    handle: Handle,
+    _marker: PhantomData<R>,
+}
+
+impl<R> Sender<R> for ScheduleTokio<R>
+where
+    R: SetValue<Value = ()> + SetStopped + Send + 'static,
+{
+    type Value = R::Value;
+    type Error = ();
+
+    type Operation = TokioOperation<R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        TokioOperation {
+            handle: self.handle,
+            receiver: Some(receiver),
+        }
+    }
+}
+
+pub struct TokioOperation<R> {
+    handle: Handle,
+    receiver: Option<R>,
+}
+
+impl<R> OperationState for TokioOperation<R>
+where
+    R: SetValue<Value = ()> + SetStopped + Send + 'static,
+{
+    fn start(&mut self) {
+        let Some(receiver) = self.receiver.take() else {
+            return;
+        };
+
+        let mut completion = StopOnDrop(Some(receiver));
+        self.handle.spawn(async move {
+            if let Some(receiver) = completion.0.take() {
+                receiver.set_value(());
+            }
+        });
+    }
+}
+
+/// Completes the receiver with stopped when the runtime drops the task
+/// without running it.
+struct StopOnDrop<R: SetStopped>(Option<R>);
+
+impl<R: SetStopped> Drop for StopOnDrop<R> {
+    fn drop(&mut self) {
+        if let Some(receiver) = self.0.take() {
+            receiver.set_stopped();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread::{self, ThreadId};
+    use tokio::runtime::Builder;
+
+    struct ChannelReceiver {
+        tx: mpsc::Sender<Option<ThreadId>>,
+    }
+
+    impl SetValue for ChannelReceiver {
+        type Value = ();
+
+        fn set_value(self, _value: Self::Value) {
+            self.tx.send(Some(thread::current().id())).unwrap();
+        }
+    }
+
+    impl SetStopped for ChannelReceiver {
+        fn set_stopped(self) {
+            self.tx.send(None).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_tokio_scheduler() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let mut scheduler = TokioScheduler::new(runtime.handle().clone());
+        let (tx, rx) = mpsc::channel();
+
+        let mut operation = scheduler.schedule().connect(ChannelReceiver { tx });
+        operation.start();
+        assert!(rx.try_recv().is_err());
+
+        // The current thread runtime runs spawned tasks while blocking on.
+        runtime.block_on(tokio::task::yield_now());
+        assert_eq!(rx.recv().unwrap(), Some(thread::current().id()));
+    }
+
+    #[test]
+    fn test_tokio_scheduler_after_shutdown() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let mut scheduler = TokioScheduler::new(runtime.handle().clone());
+        drop(runtime);
+
+        let (tx, rx) = mpsc::channel();
+        let mut operation = scheduler.schedule().connect(ChannelReceiver { tx });
+        operation.start();
+        assert_eq!(rx.recv().unwrap(), None);
+    }
+}
@@ -0,0 +1,287 @@
+use exec_core::receiver::{SetStopped, SetValue};
+use exec_core::{OperationState, Scheduler, Sender};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr::NonNull;
+
+thread_local! {
+    /// How many trampoline operations are running on this thread's stack.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// Operations deferred until the outermost one returns.
+    static DEFERRED: RefCell<VecDeque<Deferred>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// The boxed receiver of a deferred operation, which may be gone by the time
+/// it is executed.
+struct Deferred {
+    receiver: NonNull<()>,
+    execute: unsafe fn(NonNull<()>),
+    drop: unsafe fn(NonNull<()>),
+}
+
+impl Deferred {
+    fn new<R>(receiver: R) -> Self
+    where
+        R: SetValue<Value = ()>,
+    {
+        unsafe fn execute<R: SetValue<Value = ()>>(receiver: NonNull<()>) {
+            Box::from_raw(receiver.cast::<R>().as_ptr()).set_value(());
+        }
+
+        unsafe fn drop<R>(receiver: NonNull<()>) {
+            let _ = Box::from_raw(receiver.cast::<R>().as_ptr());
+        }
+
+        Self {
+            receiver: NonNull::from(Box::leak(Box::new(receiver))).cast(),
+            execute: execute::<R>,
+            drop: drop::<R>,
+        }
+    }
+
+    fn execute(self) {
+        let this = ManuallyDrop::new(self);
+        unsafe { (this.execute)(this.receiver) }
+    }
+}
+
+impl Drop for Deferred {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.receiver) }
+    }
+}
+
+/// Scheduler running work inline on the calling thread, up to a recursion
+/// depth.
+///
+/// Work scheduled deeper than that is deferred until the outermost scheduled
+/// operation returns, which bounds the stack of loops re-scheduling
+/// themselves from within their own completion.
+#[derive(Clone, Copy, Debug)]
+pub struct TrampolineScheduler {
+    max_depth: usize,
+}
+
+impl TrampolineScheduler {
+    pub fn new(max_depth: usize) -> Self {
+        assert!(max_depth > 0, "max_depth must be positive");
+        Self { max_depth }
+    }
+}
+
+impl Default for TrampolineScheduler {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl<R> Scheduler<R> for TrampolineScheduler
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    type Sender = ScheduleTrampoline<R>;
+
+    fn schedule(&mut self) -> Self::Sender {
+        ScheduleTrampoline {
+            max_depth: self.max_depth,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Sender to schedule task on the trampoline of the current thread.
+pub struct ScheduleTrampoline<R> {
+    max_depth: usize,
+    _marker: PhantomData<R>,
+}
+
+impl<R> Sender<R> for ScheduleTrampoline<R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    type Value = R::Value;
+    type Error = ();
+
+    type Operation = TrampolineOperation<R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        TrampolineOperation {
+            max_depth: self.max_depth,
+            receiver: Some(receiver),
+        }
+    }
+}
+
+pub struct TrampolineOperation<R> {
+    max_depth: usize,
+    receiver: Option<R>,
+}
+
+impl<R> OperationState for TrampolineOperation<R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    fn start(&mut self) {
+        let Some(receiver) = self.receiver.take() else {
+            return;
+        };
+        let depth = DEPTH.get();
+        if depth >= self.max_depth {
+            let deferred = Deferred::new(receiver);
+            return DEFERRED.with_borrow_mut(|deferred_ops| deferred_ops.push_back(deferred));
+        }
+
+        DEPTH.set(depth + 1);
+        let _restore = scopeguard::guard(depth, |depth| DEPTH.set(depth));
+        // The receivers deferred so far are dropped with the panic, a later
+        // trampoline must not execute them.
+        let _clear = scopeguard::guard_on_unwind(depth, |depth| {
+            if depth == 0 {
+                drop(DEFERRED.take());
+            }
+        });
+        // The operation may be gone once executed, `self` isn't touched
+        // afterwards.
+        receiver.set_value(());
+        if depth == 0 {
+            while let Some(deferred) = DEFERRED.with_borrow_mut(VecDeque::pop_front) {
+                deferred.execute();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    /// Re-schedules itself `remaining` times, recording the stack depth.
+    struct Loop {
+        scheduler: TrampolineScheduler,
+        remaining: usize,
+        operation: Option<TrampolineOperation<LoopReceiver>>,
+        max_depth: usize,
+        /// Panics once `remaining` reaches that count.
+        panic_at: Option<usize>,
+    }
+
+    struct LoopReceiver(NonNull<Loop>);
+
+    impl SetValue for LoopReceiver {
+        type Value = ();
+
+        fn set_value(self, _value: Self::Value) {
+            let this = unsafe { &mut *self.0.as_ptr() };
+            this.max_depth = this.max_depth.max(DEPTH.get());
+            if this.remaining > 0 {
+                this.remaining -= 1;
+                this.schedule();
+            }
+            if this.panic_at == Some(this.remaining) {
+                panic!("loop panicked");
+            }
+        }
+    }
+
+    impl SetStopped for LoopReceiver {
+        fn set_stopped(self) {
+            unreachable!();
+        }
+    }
+
+    impl Loop {
+        fn schedule(&mut self) {
+            let receiver = LoopReceiver(NonNull::from(&mut *self));
+            self.operation
+                .insert(self.scheduler.schedule().connect(receiver))
+                .start();
+        }
+    }
+
+    #[test]
+    fn test_trampoline() {
+        let mut looping = Loop {
+            scheduler: TrampolineScheduler::new(4),
+            remaining: 100_000,
+            operation: None,
+            max_depth: 0,
+            panic_at: None,
+        };
+        looping.schedule();
+        assert_eq!(looping.remaining, 0);
+        assert_eq!(looping.max_depth, 4);
+        assert_eq!(DEPTH.get(), 0);
+    }
+
+    #[test]
+    fn test_trampoline_panic() {
+        // Panics right after the third run has been deferred.
+        let mut looping = Loop {
+            scheduler: TrampolineScheduler::new(2),
+            remaining: 10,
+            operation: None,
+            max_depth: 0,
+            panic_at: Some(8),
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| looping.schedule()));
+        assert!(result.is_err());
+        assert_eq!(DEPTH.get(), 0);
+        assert!(DEFERRED.with_borrow(VecDeque::is_empty));
+    }
+
+    /// Schedules setting `inner` from within its completion and drops the operation
+    /// right away.
+    struct Nested {
+        scheduler: TrampolineScheduler,
+        inner: Rc<Cell<bool>>,
+    }
+
+    struct Flag(Rc<Cell<bool>>);
+
+    impl SetValue for Nested {
+        type Value = ();
+
+        fn set_value(mut self, _value: Self::Value) {
+            let mut operation = self.scheduler.schedule().connect(Flag(self.inner.clone()));
+            operation.start();
+            drop(operation);
+            assert!(!self.inner.get());
+        }
+    }
+
+    impl SetValue for Flag {
+        type Value = ();
+
+        fn set_value(self, _value: Self::Value) {
+            self.0.set(true);
+        }
+    }
+
+    impl SetStopped for Nested {
+        fn set_stopped(self) {
+            unreachable!();
+        }
+    }
+
+    impl SetStopped for Flag {
+        fn set_stopped(self) {
+            unreachable!();
+        }
+    }
+
+    #[test]
+    fn test_trampoline_deferred_operation_dropped() {
+        let mut scheduler = TrampolineScheduler::new(1);
+        let inner = Rc::new(Cell::new(false));
+        let receiver = Nested {
+            scheduler,
+            inner: inner.clone(),
+        };
+        scheduler.schedule().connect(receiver).start();
+        assert!(inner.get());
+    }
+}
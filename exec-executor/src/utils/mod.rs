@@ -1 +1,3 @@
 pub(crate) mod linked_list;
+pub(crate) mod mpsc_queue;
+pub(crate) mod parker;
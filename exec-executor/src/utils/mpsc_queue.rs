@@ -0,0 +1,254 @@
+//! An intrusive multi-producer single-consumer queue.
+//!
+//! This is the non-blocking queue described by Dmitry Vyukov: producers only
+//! perform a single atomic swap on the head, the consumer owns the tail and
+//! never synchronizes with other consumers. Nodes are linked through an
+//! `AtomicPtr` embedded in the node itself, so pushing never allocates.
+//!
+//! The queue hands out nodes in FIFO order. A pop can observe a producer that
+//! has swapped the head but not yet linked its node, in which case the queue
+//! reports itself as inconsistent and the consumer should retry shortly.
+
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// Defines how a type is linked within a [`MpscQueue`].
+///
+/// # Safety
+///
+/// Implementations must guarantee that `Target` types are pinned in memory
+/// while they are stored in the queue.
+pub(crate) unsafe trait Link {
+    /// Node type.
+    type Target;
+
+    /// Return the next pointer of a node.
+    ///
+    /// # Safety
+    ///
+    /// The resulting pointer should not be derived from an intermediate
+    /// reference to the whole node.
+    unsafe fn next(target: NonNull<Self::Target>) -> NonNull<AtomicPtr<Self::Target>>;
+}
+
+/// Result of [`MpscQueue::pop`].
+pub(crate) enum Pop<T> {
+    /// A node was dequeued.
+    Data(NonNull<T>),
+    /// The queue is empty.
+    Empty,
+    /// A producer is in the middle of a push, the node will show up soon.
+    Inconsistent,
+}
+
+pub(crate) struct MpscQueue<L: Link> {
+    /// Most recently pushed node, shared by all producers.
+    head: AtomicPtr<L::Target>,
+
+    /// Oldest node, only accessed by the consumer.
+    tail: UnsafeCell<NonNull<L::Target>>,
+
+    /// Placeholder node keeping the list non-empty.
+    stub: NonNull<L::Target>,
+
+    _marker: PhantomData<*const L>,
+}
+
+unsafe impl<L: Link> Send for MpscQueue<L> where L::Target: Send {}
+unsafe impl<L: Link> Sync for MpscQueue<L> where L::Target: Send {}
+
+impl<L: Link> MpscQueue<L> {
+    /// Creates an empty queue, `stub` is never handed out to the consumer.
+    pub(crate) fn new(stub: Box<L::Target>) -> MpscQueue<L> {
+        let stub = NonNull::from(Box::leak(stub));
+        unsafe {
+            L::next(stub)
+                .as_ref()
+                .store(ptr::null_mut(), Ordering::Relaxed);
+        }
+        MpscQueue {
+            head: AtomicPtr::new(stub.as_ptr()),
+            tail: UnsafeCell::new(stub),
+            stub,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds a node at the head of the queue, may be called from any thread.
+    ///
+    /// # Safety
+    ///
+    /// The node must stay pinned and must not be contained by any queue until
+    /// it has been popped.
+    pub(crate) unsafe fn push(&self, node: NonNull<L::Target>) {
+        L::next(node)
+            .as_ref()
+            .store(ptr::null_mut(), Ordering::Relaxed);
+        let prev = self.head.swap(node.as_ptr(), Ordering::AcqRel);
+        L::next(NonNull::new_unchecked(prev))
+            .as_ref()
+            .store(node.as_ptr(), Ordering::Release);
+    }
+
+    /// Removes the oldest node from the queue.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called by a single consumer at a time.
+    pub(crate) unsafe fn pop(&self) -> Pop<L::Target> {
+        let mut tail = *self.tail.get();
+        let mut next = L::next(tail).as_ref().load(Ordering::Acquire);
+
+        if tail == self.stub {
+            match NonNull::new(next) {
+                None => return Pop::Empty,
+                Some(node) => {
+                    *self.tail.get() = node;
+                    tail = node;
+                    next = L::next(node).as_ref().load(Ordering::Acquire);
+                }
+            }
+        }
+
+        if let Some(node) = NonNull::new(next) {
+            *self.tail.get() = node;
+            return Pop::Data(tail);
+        }
+
+        if tail.as_ptr() != self.head.load(Ordering::Acquire) {
+            return Pop::Inconsistent;
+        }
+
+        // `tail` is the last node, put the stub back behind it so that it can
+        // be unlinked.
+        self.push(self.stub);
+
+        next = L::next(tail).as_ref().load(Ordering::Acquire);
+        if let Some(node) = NonNull::new(next) {
+            *self.tail.get() = node;
+            return Pop::Data(tail);
+        }
+
+        Pop::Inconsistent
+    }
+}
+
+impl<L: Link> Drop for MpscQueue<L> {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(self.stub.as_ptr()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    struct Entry {
+        next: AtomicPtr<Entry>,
+        val: usize,
+    }
+
+    impl Entry {
+        fn new(val: usize) -> Entry {
+            Entry {
+                next: AtomicPtr::new(ptr::null_mut()),
+                val,
+            }
+        }
+    }
+
+    generate_addr_of_methods! {
+        impl<> Entry {
+            unsafe fn addr_of_next(self: NonNull<Self>) -> NonNull<AtomicPtr<Entry>> {
+                &self.next
+            }
+        }
+    }
+
+    unsafe impl Link for Entry {
+        type Target = Entry;
+
+        unsafe fn next(target: NonNull<Entry>) -> NonNull<AtomicPtr<Entry>> {
+            Entry::addr_of_next(target)
+        }
+    }
+
+    fn pop_blocking(queue: &MpscQueue<Entry>) -> Option<NonNull<Entry>> {
+        loop {
+            match unsafe { queue.pop() } {
+                Pop::Data(node) => return Some(node),
+                Pop::Empty => return None,
+                Pop::Inconsistent => thread::yield_now(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let queue = MpscQueue::<Entry>::new(Box::new(Entry::new(0)));
+        let a = Entry::new(1);
+        let b = Entry::new(2);
+
+        assert!(pop_blocking(&queue).is_none());
+        unsafe {
+            queue.push(NonNull::from(&a));
+            queue.push(NonNull::from(&b));
+        }
+
+        let e = unsafe { pop_blocking(&queue).unwrap().as_ref() };
+        assert_eq!(e.val, 1);
+        let e = unsafe { pop_blocking(&queue).unwrap().as_ref() };
+        assert_eq!(e.val, 2);
+        assert!(pop_blocking(&queue).is_none());
+
+        // The stub has been recycled, the queue is still usable.
+        unsafe { queue.push(NonNull::from(&a)) };
+        let e = unsafe { pop_blocking(&queue).unwrap().as_ref() };
+        assert_eq!(e.val, 1);
+    }
+
+    #[test]
+    fn test_multiple_producers() {
+        const PRODUCERS: usize = 4;
+        const ITEMS: usize = 1000;
+
+        let queue = Arc::new(MpscQueue::<Entry>::new(Box::new(Entry::new(0))));
+        let entries: Arc<Vec<Entry>> = Arc::new((0..PRODUCERS * ITEMS).map(Entry::new).collect());
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let queue = queue.clone();
+                let entries = entries.clone();
+                thread::spawn(move || {
+                    for entry in &entries[p * ITEMS..(p + 1) * ITEMS] {
+                        unsafe { queue.push(NonNull::from(entry)) };
+                    }
+                })
+            })
+            .collect();
+
+        let mut last = [None; PRODUCERS];
+        let mut received = 0;
+        while received < PRODUCERS * ITEMS {
+            if let Some(node) = pop_blocking(&queue) {
+                let val = unsafe { node.as_ref().val };
+                // Items of a single producer come out in the order pushed.
+                let producer = val / ITEMS;
+                assert!(last[producer].is_none_or(|prev| prev < val));
+                last[producer] = Some(val);
+                received += 1;
+            }
+        }
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert!(pop_blocking(&queue).is_none());
+    }
+}
@@ -0,0 +1,117 @@
+//! A thread parker used by consumers that have run out of work.
+//!
+//! Unlike `std::thread::park`, the parker is not bound to a particular thread,
+//! so it can be owned by a run loop that may be driven from any thread. The
+//! mutex is only touched when the consumer actually goes to sleep, producers
+//! notifying an awake consumer only pay for a single atomic swap.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+const EMPTY: usize = 0;
+const PARKED: usize = 1;
+const NOTIFIED: usize = 2;
+
+pub(crate) struct Parker {
+    state: AtomicUsize,
+    lock: Mutex<()>,
+    cvar: Condvar,
+}
+
+impl Parker {
+    pub(crate) const fn new() -> Parker {
+        Parker {
+            state: AtomicUsize::new(EMPTY),
+            lock: Mutex::new(()),
+            cvar: Condvar::new(),
+        }
+    }
+
+    /// Blocks the current thread until `unpark` is called.
+    ///
+    /// A notification sent while nobody is parked is remembered, the next call
+    /// to `park` then returns immediately.
+    pub(crate) fn park(&self) {
+        // Fast path, consume a pending notification without locking.
+        if self
+            .state
+            .compare_exchange(NOTIFIED, EMPTY, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            return;
+        }
+
+        let mut guard = self.lock.lock().unwrap();
+        match self
+            .state
+            .compare_exchange(EMPTY, PARKED, Ordering::SeqCst, Ordering::SeqCst)
+        {
+            Ok(_) => {}
+            Err(NOTIFIED) => {
+                // Notified between the fast path and taking the lock.
+                self.state.swap(EMPTY, Ordering::SeqCst);
+                return;
+            }
+            Err(actual) => panic!("inconsistent park state: {}", actual),
+        }
+
+        loop {
+            guard = self.cvar.wait(guard).unwrap();
+            if self
+                .state
+                .compare_exchange(NOTIFIED, EMPTY, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                return;
+            }
+            // Spurious wake up, go back to sleep.
+        }
+    }
+
+    /// Like `park`, but gives up after `timeout`.
+    pub(crate) fn park_timeout(&self, timeout: Duration) {
+        if self
+            .state
+            .compare_exchange(NOTIFIED, EMPTY, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            return;
+        }
+
+        let guard = self.lock.lock().unwrap();
+        match self
+            .state
+            .compare_exchange(EMPTY, PARKED, Ordering::SeqCst, Ordering::SeqCst)
+        {
+            Ok(_) => {}
+            Err(NOTIFIED) => {
+                self.state.swap(EMPTY, Ordering::SeqCst);
+                return;
+            }
+            Err(actual) => panic!("inconsistent park_timeout state: {}", actual),
+        }
+
+        // Whether we were notified, timed out or woke up spuriously, the
+        // caller re-checks its condition anyway.
+        let _ = self.cvar.wait_timeout(guard, timeout).unwrap();
+        match self.state.swap(EMPTY, Ordering::SeqCst) {
+            NOTIFIED | PARKED => {}
+            actual => panic!("inconsistent park_timeout state: {}", actual),
+        }
+    }
+
+    /// Wakes up the parked consumer, or makes its next `park` return at once.
+    pub(crate) fn unpark(&self) {
+        match self.state.swap(NOTIFIED, Ordering::SeqCst) {
+            EMPTY | NOTIFIED => return,
+            PARKED => {}
+            actual => panic!("inconsistent state in unpark: {}", actual),
+        }
+
+        // Take the lock so the consumer is guaranteed to be waiting on the
+        // condition variable before we notify it.
+        drop(self.lock.lock().unwrap());
+        self.cvar.notify_one();
+    }
+}
@@ -1,2 +1,4 @@
 pub mod errors;
 pub mod receivers;
+pub mod schedulers;
+pub mod senders;
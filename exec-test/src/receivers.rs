@@ -1,6 +1,10 @@
-use exec_core::receiver::{SetError, SetValue};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{Sender, StopSource, StopToken};
 use std::error::Error;
 use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::{mpsc, Arc, Mutex};
 
 pub struct ExpectValueReceiver<T> {
     expected: T,
@@ -24,6 +28,12 @@ where
     }
 }
 
+impl<T: Debug> SetStopped for ExpectValueReceiver<T> {
+    fn set_stopped(self) {
+        panic!("Expected: {:?}, Actual: stopped", self.expected);
+    }
+}
+
 pub struct ExpectErrorReceiver<E> {
     expected: E,
 }
@@ -42,3 +52,232 @@ impl<E: Error + PartialEq> SetError for ExpectErrorReceiver<E> {
         assert_eq!(self.expected, error);
     }
 }
+
+pub struct ExpectStoppedReceiver<T> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T> ExpectStoppedReceiver<T> {
+    pub fn new() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for ExpectStoppedReceiver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> SetValue for ExpectStoppedReceiver<T> {
+    type Value = T;
+
+    fn set_value(self, value: Self::Value) {
+        panic!("Expected: stopped, Actual: {:?}", value);
+    }
+}
+
+impl<T> SetStopped for ExpectStoppedReceiver<T> {
+    fn set_stopped(self) {
+        println!("Expected: stopped, Actual: stopped");
+    }
+}
+
+/// How a sender completed a [`ChannelReceiver`].
+#[derive(Debug, PartialEq)]
+pub enum Completed<V, E> {
+    Value(V),
+    Error(E),
+    Stopped,
+}
+
+/// A receiver sending its completion over a channel, for senders completing
+/// later on.
+pub struct ChannelReceiver<V, E> {
+    stop_source: StopSource,
+    done: mpsc::Sender<Completed<V, E>>,
+}
+
+impl<V, E> ChannelReceiver<V, E> {
+    pub fn new() -> (Self, mpsc::Receiver<Completed<V, E>>) {
+        let (done, completed) = mpsc::channel();
+        let receiver = Self {
+            stop_source: StopSource::new(),
+            done,
+        };
+        (receiver, completed)
+    }
+
+    pub fn stop_source(&self) -> StopSource {
+        self.stop_source.clone()
+    }
+}
+
+impl<V, E> SetValue for ChannelReceiver<V, E> {
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        self.done.send(Completed::Value(value)).unwrap();
+    }
+}
+
+impl<V, E: Error> SetError for ChannelReceiver<V, E> {
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        self.done.send(Completed::Error(error)).unwrap();
+    }
+}
+
+impl<V, E> SetStopped for ChannelReceiver<V, E> {
+    fn set_stopped(self) {
+        self.done.send(Completed::Stopped).unwrap();
+    }
+}
+
+impl<V, E> GetStopToken for ChannelReceiver<V, E> {
+    fn get_stop_token(&self) -> StopToken {
+        self.stop_source.token()
+    }
+}
+
+/// How a sequence completed a [`CollectReceiver`], with the items it got.
+#[derive(Debug, PartialEq)]
+pub enum Collected<T> {
+    Value(Vec<T>),
+    Stopped(Vec<T>),
+}
+
+/// A sequence receiver collecting the items, it requests stop once it got
+/// `limit` of them.
+pub struct CollectReceiver<T> {
+    items: Arc<Mutex<Vec<T>>>,
+    limit: usize,
+    stop_source: StopSource,
+    done: mpsc::Sender<Collected<T>>,
+}
+
+impl<T> CollectReceiver<T> {
+    pub fn new() -> (Self, mpsc::Receiver<Collected<T>>) {
+        Self::with_limit(usize::MAX)
+    }
+
+    pub fn with_limit(limit: usize) -> (Self, mpsc::Receiver<Collected<T>>) {
+        let (done, collected) = mpsc::channel();
+        let receiver = Self {
+            items: Arc::new(Mutex::new(Vec::new())),
+            limit,
+            stop_source: StopSource::new(),
+            done,
+        };
+        (receiver, collected)
+    }
+
+    pub fn stop_source(&self) -> StopSource {
+        self.stop_source.clone()
+    }
+
+    fn take_items(&self) -> Vec<T> {
+        std::mem::take(&mut *self.items.lock().unwrap())
+    }
+}
+
+impl<S, T> SetNext<S> for CollectReceiver<T>
+where
+    S: Sender<CollectItemReceiver<T>>,
+{
+    type Next = CollectNext<S, T>;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        CollectNext {
+            item,
+            items: self.items.clone(),
+            limit: self.limit,
+            stop_source: self.stop_source.clone(),
+        }
+    }
+}
+
+impl<T> SetValue for CollectReceiver<T> {
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.done.send(Collected::Value(self.take_items())).unwrap();
+    }
+}
+
+impl<T> SetStopped for CollectReceiver<T> {
+    fn set_stopped(self) {
+        self.done
+            .send(Collected::Stopped(self.take_items()))
+            .unwrap();
+    }
+}
+
+impl<T> GetStopToken for CollectReceiver<T> {
+    fn get_stop_token(&self) -> StopToken {
+        self.stop_source.token()
+    }
+}
+
+/// Consumes an item of a [`CollectReceiver`].
+pub struct CollectNext<S, T> {
+    item: S,
+    items: Arc<Mutex<Vec<T>>>,
+    limit: usize,
+    stop_source: StopSource,
+}
+
+impl<S, T> Sender<NextReceiver> for CollectNext<S, T>
+where
+    S: Sender<CollectItemReceiver<T>>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = S::Operation;
+
+    fn connect(self, receiver: NextReceiver) -> Self::Operation {
+        self.item.connect(CollectItemReceiver {
+            items: self.items,
+            limit: self.limit,
+            stop_source: self.stop_source,
+            receiver,
+        })
+    }
+}
+
+pub struct CollectItemReceiver<T> {
+    items: Arc<Mutex<Vec<T>>>,
+    limit: usize,
+    stop_source: StopSource,
+    receiver: NextReceiver,
+}
+
+impl<T> SetValue for CollectItemReceiver<T> {
+    type Value = T;
+
+    fn set_value(self, value: Self::Value) {
+        let mut items = self.items.lock().unwrap();
+        items.push(value);
+        if items.len() >= self.limit {
+            self.stop_source.request_stop();
+        }
+        drop(items);
+        self.receiver.set_value(());
+    }
+}
+
+impl<T> SetStopped for CollectItemReceiver<T> {
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<T> GetStopToken for CollectItemReceiver<T> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
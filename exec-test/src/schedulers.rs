@@ -0,0 +1,169 @@
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::{OperationState, Scheduler, Sender, StopCallback, TimedScheduler};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Completes a timer, `true` when it fires and `false` when it is cancelled.
+type Timer = Box<dyn FnOnce(bool) + Send>;
+
+struct Clock {
+    now: Instant,
+    timers: BTreeMap<(Instant, u64), Timer>,
+    next_id: u64,
+}
+
+/// A timed scheduler on a virtual clock, time only passes when the test
+/// advances it.
+///
+/// Timers fire inline on the thread advancing the clock, in deadline order.
+/// Plain schedules complete inline once started.
+#[derive(Clone)]
+pub struct VirtualScheduler {
+    clock: Arc<Mutex<Clock>>,
+}
+
+impl VirtualScheduler {
+    pub fn new() -> Self {
+        Self {
+            clock: Arc::new(Mutex::new(Clock {
+                now: Instant::now(),
+                timers: BTreeMap::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Moves the clock forward by `duration`, firing the timers due by then.
+    pub fn advance(&self, duration: Duration) {
+        let deadline = self.clock.lock().unwrap().now + duration;
+        loop {
+            let mut clock = self.clock.lock().unwrap();
+            let Some(entry) = clock.timers.first_entry() else {
+                break;
+            };
+            if entry.key().0 > deadline {
+                break;
+            }
+            let ((due, _), timer) = entry.remove_entry();
+            clock.now = due;
+            drop(clock);
+            timer(true);
+        }
+        self.clock.lock().unwrap().now = deadline;
+    }
+
+    /// Number of timers waiting to fire.
+    pub fn pending_timers(&self) -> usize {
+        self.clock.lock().unwrap().timers.len()
+    }
+}
+
+impl Default for VirtualScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> Scheduler<R> for VirtualScheduler
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken + Send + 'static,
+{
+    type Sender = VirtualTimer<R>;
+
+    fn schedule(&mut self) -> Self::Sender {
+        let now = self.clock.lock().unwrap().now;
+        self.schedule_at(now)
+    }
+}
+
+impl<R> TimedScheduler<R> for VirtualScheduler
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken + Send + 'static,
+{
+    type TimedSender = VirtualTimer<R>;
+
+    fn now(&self) -> Instant {
+        self.clock.lock().unwrap().now
+    }
+
+    fn schedule_at(&mut self, deadline: Instant) -> Self::TimedSender {
+        VirtualTimer {
+            clock: self.clock.clone(),
+            deadline,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Completes once the virtual clock reaches `deadline`.
+pub struct VirtualTimer<R> {
+    clock: Arc<Mutex<Clock>>,
+    deadline: Instant,
+    _marker: PhantomData<R>,
+}
+
+impl<R> Sender<R> for VirtualTimer<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken + Send + 'static,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = VirtualTimerOperation<R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        VirtualTimerOperation {
+            clock: self.clock,
+            deadline: self.deadline,
+            receiver: Some(receiver),
+            stop_callback: None,
+        }
+    }
+}
+
+pub struct VirtualTimerOperation<R> {
+    clock: Arc<Mutex<Clock>>,
+    deadline: Instant,
+    receiver: Option<R>,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<R> OperationState for VirtualTimerOperation<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken + Send + 'static,
+{
+    fn start(&mut self) {
+        let receiver = self.receiver.take().unwrap();
+        let token = receiver.get_stop_token();
+        if token.stop_requested() {
+            return receiver.set_stopped();
+        }
+
+        let mut clock = self.clock.lock().unwrap();
+        if self.deadline <= clock.now {
+            drop(clock);
+            return receiver.set_value(());
+        }
+        let key = (self.deadline, clock.next_id);
+        clock.next_id += 1;
+        let timer: Timer = Box::new(move |fired| {
+            if fired {
+                receiver.set_value(());
+            } else {
+                receiver.set_stopped();
+            }
+        });
+        clock.timers.insert(key, timer);
+        drop(clock);
+
+        let clock = self.clock.clone();
+        self.stop_callback = Some(StopCallback::new(&token, move || {
+            let timer = clock.lock().unwrap().timers.remove(&key);
+            if let Some(timer) = timer {
+                timer(false);
+            }
+        }));
+    }
+}
@@ -0,0 +1,77 @@
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopCallback};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A sender that never produces a value, it completes with stopped once stop
+/// is requested through the receiver's stop token.
+pub struct NeverSender<T> {
+    stopped: Arc<AtomicBool>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> NeverSender<T> {
+    pub fn new() -> Self {
+        Self {
+            stopped: Arc::new(AtomicBool::new(false)),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Flag raised once the operation has completed with stopped.
+    pub fn stopped(&self) -> Arc<AtomicBool> {
+        self.stopped.clone()
+    }
+}
+
+impl<T> Default for NeverSender<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct NeverOperation<R> {
+    stopped: Arc<AtomicBool>,
+    receiver: Option<R>,
+    callback: Option<StopCallback>,
+}
+
+struct OperationPtr<R>(*mut NeverOperation<R>);
+
+unsafe impl<R> Send for OperationPtr<R> {}
+
+impl<R> OperationState for NeverOperation<R>
+where
+    R: SetStopped + GetStopToken + 'static,
+{
+    fn start(&mut self) {
+        let token = self.receiver.as_ref().unwrap().get_stop_token();
+        let op = OperationPtr(self as *mut Self);
+        self.callback = Some(StopCallback::new(&token, move || {
+            let op = op;
+            if let Some(receiver) = unsafe { (*op.0).receiver.take() } {
+                unsafe { (*op.0).stopped.store(true, Ordering::SeqCst) };
+                receiver.set_stopped();
+            }
+        }));
+    }
+}
+
+impl<T, R> Sender<R> for NeverSender<T>
+where
+    R: SetValue<Value = T> + SetStopped + GetStopToken + 'static,
+{
+    type Value = T;
+    type Error = ();
+
+    type Operation = NeverOperation<R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        NeverOperation {
+            stopped: self.stopped,
+            receiver: Some(receiver),
+            callback: None,
+        }
+    }
+}
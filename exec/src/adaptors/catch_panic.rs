@@ -0,0 +1,248 @@
+use exec_core::panic::{self, PanicError};
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopToken};
+use std::panic::resume_unwind;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+/// Completes with a [`PanicError`], converted into the error type of the
+/// receiver, when a panic unwinds through the receiver,
+/// e.g. from a `then` closure, instead of losing the completion.
+///
+/// The panic is delivered once caught, inline by the operation itself or by
+/// the run loop of the context it happened on, see
+/// [`PanicPolicy::Contain`](exec_executor::PanicPolicy::Contain). A panic
+/// escaping the start of the operation is delivered by the operation even if
+/// it didn't unwind through the receiver, as long as the receiver hasn't
+/// completed yet.
+pub fn catch_panic<S>(sender: S) -> CatchPanic<S> {
+    CatchPanic::new(sender)
+}
+
+pub struct CatchPanic<S> {
+    sender: S,
+}
+
+impl<S> CatchPanic<S> {
+    pub fn new(sender: S) -> Self {
+        Self { sender }
+    }
+}
+
+/// The receiver, shared by the receiver handed to the sender and the
+/// operation, whichever completes it first takes it.
+type Slot<R> = Arc<Mutex<Option<R>>>;
+
+fn take<R>(slot: &Slot<R>) -> Option<R> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner).take()
+}
+
+pub struct CatchPanicReceiver<R> {
+    receiver: Slot<R>,
+    /// Registers the receiver to complete with the panic unwinding the
+    /// thread. Stored as a function pointer since `Drop` can't require more
+    /// of `R` than the struct does.
+    on_unwind: fn(R),
+}
+
+impl<R> CatchPanicReceiver<R> {
+    fn take(&self) -> R {
+        take(&self.receiver).unwrap()
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&R) -> T) -> T {
+        let receiver = self.receiver.lock().unwrap_or_else(PoisonError::into_inner);
+        f(receiver.as_ref().unwrap())
+    }
+}
+
+fn on_unwind<R>(receiver: R)
+where
+    R: SetError + 'static,
+    R::Error: From<PanicError>,
+{
+    panic::on_unwind(move |payload| receiver.set_error(PanicError::new(payload).into()));
+}
+
+impl<R> Drop for CatchPanicReceiver<R> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            if let Some(receiver) = take(&self.receiver) {
+                (self.on_unwind)(receiver);
+            }
+        }
+    }
+}
+
+impl<R: SetValue> SetValue for CatchPanicReceiver<R> {
+    type Value = R::Value;
+
+    fn set_value(self, value: Self::Value) {
+        self.take().set_value(value);
+    }
+}
+
+impl<R: SetError> SetError for CatchPanicReceiver<R> {
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.take().set_error(error);
+    }
+}
+
+impl<R: SetStopped> SetStopped for CatchPanicReceiver<R> {
+    fn set_stopped(self) {
+        self.take().set_stopped();
+    }
+}
+
+impl<R: GetStopToken> GetStopToken for CatchPanicReceiver<R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.with(R::get_stop_token)
+    }
+}
+
+impl<R: GetScheduler> GetScheduler for CatchPanicReceiver<R> {
+    type Scheduler = R::Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        self.with(R::get_scheduler)
+    }
+}
+
+pub struct CatchPanicOperation<O, R> {
+    operation: O,
+    receiver: Slot<R>,
+}
+
+impl<O, R> OperationState for CatchPanicOperation<O, R>
+where
+    O: OperationState,
+    R: SetError,
+    R::Error: From<PanicError>,
+{
+    fn start(&mut self) {
+        if let Err(Some(payload)) = panic::catch_unwind(|| self.operation.start()) {
+            match take(&self.receiver) {
+                Some(receiver) => receiver.set_error(PanicError::new(payload).into()),
+                // The receiver completed already, the panic isn't ours.
+                None => resume_unwind(payload),
+            }
+        }
+    }
+}
+
+impl<S, R> Sender<R> for CatchPanic<S>
+where
+    S: Sender<CatchPanicReceiver<R>>,
+    R: SetError + 'static,
+    R::Error: From<PanicError>,
+{
+    type Value = S::Value;
+    type Error = R::Error;
+
+    type Operation = CatchPanicOperation<S::Operation, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let receiver = Arc::new(Mutex::new(Some(receiver)));
+        CatchPanicOperation {
+            operation: self.sender.connect(CatchPanicReceiver {
+                receiver: receiver.clone(),
+                on_unwind: on_unwind::<R>,
+            }),
+            receiver,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumers::{sync_wait, sync_wait_for};
+    use crate::factories::{from_future_on, just};
+    use crate::then;
+    use exec_core::Scheduler;
+    use exec_executor::SingleThreadContext;
+    use std::thread::ThreadId;
+    use std::time::Duration;
+
+    #[test]
+    fn test_catch_panic() {
+        let sender = catch_panic(then(just(1), |x: i32| x + 1));
+        assert_eq!(sync_wait::<_, _, PanicError>(sender).unwrap(), Some(2));
+
+        let sender = catch_panic(then(just(1), |_: i32| -> i32 { panic!("boom") }));
+        let error = sync_wait::<_, _, PanicError>(sender).unwrap_err();
+        assert_eq!(error.message(), Some("boom"));
+
+        // Converted into the error type of the receiver.
+        let sender = catch_panic(then(just(1), |_: i32| -> i32 { panic!("boom") }));
+        let error = sync_wait::<_, _, crate::Error>(sender).unwrap_err();
+        assert_eq!(
+            error.downcast::<PanicError>().unwrap().message(),
+            Some("boom")
+        );
+    }
+
+    #[test]
+    fn test_catch_panic_on_context() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+
+        let sender = then(scheduler.schedule(), |_| -> ThreadId { panic!("boom") });
+        let error = sync_wait::<_, _, PanicError>(catch_panic(sender)).unwrap_err();
+        assert_eq!(error.message(), Some("boom"));
+
+        // The context survived the panic.
+        let sender = then(scheduler.schedule(), |_| thread::current().id());
+        let id = sync_wait::<_, _, PanicError>(catch_panic(sender))
+            .unwrap()
+            .unwrap();
+        assert_ne!(id, thread::current().id());
+    }
+
+    #[test]
+    fn test_catch_panic_future() {
+        let context = SingleThreadContext::new();
+        let sender = from_future_on(context.get_scheduler(), async {
+            if true {
+                panic!("boom");
+            }
+            Ok::<(), PanicError>(())
+        });
+        let error = sync_wait_for(catch_panic(sender), Duration::from_secs(5)).unwrap_err();
+        assert_eq!(error.to_string(), "panicked: boom");
+    }
+
+    /// Panics when started, without dropping its receiver.
+    struct PanicOnStart;
+
+    struct PanicOnStartOperation<R> {
+        _receiver: R,
+    }
+
+    impl<R> OperationState for PanicOnStartOperation<R> {
+        fn start(&mut self) {
+            panic!("boom");
+        }
+    }
+
+    impl<R> Sender<R> for PanicOnStart {
+        type Value = ();
+        type Error = ();
+
+        type Operation = PanicOnStartOperation<R>;
+
+        fn connect(self, receiver: R) -> Self::Operation {
+            PanicOnStartOperation {
+                _receiver: receiver,
+            }
+        }
+    }
+
+    #[test]
+    fn test_catch_panic_on_start() {
+        let error = sync_wait::<_, (), PanicError>(catch_panic(PanicOnStart)).unwrap_err();
+        assert_eq!(error.message(), Some("boom"));
+    }
+}
@@ -0,0 +1,186 @@
+use super::iteration::{Completion, IterationReceiver};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopToken};
+use std::ptr::NonNull;
+
+/// Runs `cleanup` once `sender` completes, however it completes, then
+/// forwards the completion of `sender`.
+///
+/// The completion is kept in the operation while `cleanup` runs, connected on
+/// the thread `sender` completed on, hence it must be `Send`, and so must its
+/// operation, dropped along with the finally operation. `cleanup`
+/// isn't stopped along with `sender`, its stop token never requests stop.
+/// An error of `cleanup` takes precedence over the completion of `sender`,
+/// which is forwarded otherwise, also when `cleanup` completes with stopped.
+pub fn finally<S, C>(sender: S, cleanup: C) -> Finally<S, C> {
+    Finally::new(sender, cleanup)
+}
+
+pub struct Finally<S, C> {
+    sender: S,
+    cleanup: C,
+}
+
+impl<S, C> Finally<S, C> {
+    pub fn new(sender: S, cleanup: C) -> Self {
+        Self { sender, cleanup }
+    }
+}
+
+pub struct FinallyOperation<S, C, R>
+where
+    S: Sender<IterationReceiver<R::Value, R::Error, R>>,
+    C: Sender<IterationReceiver<(), R::Error, R>>,
+    R: SetValue + SetError,
+{
+    sender: Option<S>,
+    cleanup: Option<C>,
+    receiver: Option<R>,
+    /// The completion of `sender`, until `cleanup` has completed.
+    completion: Option<Completion<R::Value, R::Error>>,
+    operation: Option<S::Operation>,
+    cleanup_operation: Option<C::Operation>,
+}
+
+impl<S, C, R> FinallyOperation<S, C, R>
+where
+    S: Sender<IterationReceiver<R::Value, R::Error, R>>,
+    C: Sender<IterationReceiver<(), R::Error, R>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    unsafe fn complete(op: NonNull<()>, completion: Completion<R::Value, R::Error>) {
+        let this = &mut *op.cast::<Self>().as_ptr();
+        this.completion = Some(completion);
+
+        let receiver = IterationReceiver::new(op, Self::clean_up, StopToken::never());
+        let cleanup = this.cleanup.take().unwrap();
+        this.cleanup_operation
+            .insert(cleanup.connect(receiver))
+            .start();
+    }
+
+    unsafe fn clean_up(op: NonNull<()>, completion: Completion<(), R::Error>) {
+        let this = &mut *op.cast::<Self>().as_ptr();
+        let receiver = this.receiver.take().unwrap();
+        match completion {
+            Completion::Error(error) => receiver.set_error(error),
+            Completion::Value(()) | Completion::Stopped => {
+                this.completion.take().unwrap().complete(receiver)
+            }
+        }
+    }
+}
+
+impl<S, C, R> OperationState for FinallyOperation<S, C, R>
+where
+    S: Sender<IterationReceiver<R::Value, R::Error, R>>,
+    C: Sender<IterationReceiver<(), R::Error, R>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    fn start(&mut self) {
+        let op = NonNull::from(&mut *self).cast();
+        let stop_token = self.receiver.as_ref().unwrap().get_stop_token();
+        let receiver = unsafe { IterationReceiver::new(op, Self::complete, stop_token) };
+        let sender = self.sender.take().unwrap();
+        self.operation.insert(sender.connect(receiver)).start();
+    }
+}
+
+impl<S, C, R> Sender<R> for Finally<S, C>
+where
+    S: Sender<IterationReceiver<R::Value, R::Error, R>>,
+    C: Sender<IterationReceiver<(), R::Error, R>> + Send,
+    C::Operation: Send,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    type Value = R::Value;
+    type Error = R::Error;
+
+    type Operation = FinallyOperation<S, C, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        FinallyOperation {
+            sender: Some(self.sender),
+            cleanup: Some(self.cleanup),
+            receiver: Some(receiver),
+            completion: None,
+            operation: None,
+            cleanup_operation: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::{dematerialize, then};
+    use crate::consumers::WaitResult;
+    use crate::consumers::{sync_wait, sync_wait_for, SyncWaitError};
+    use crate::factories::{just, just_error};
+    use exec_core::Scheduler;
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use exec_test::senders::NeverSender;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// A cleanup recording that it ran, after whatever ran before it.
+    fn record<M: Send>(
+        events: &Arc<Mutex<Vec<&'static str>>>,
+    ) -> impl Sender<IterationReceiver<(), TestError, M>, Operation: Send> {
+        let events = events.clone();
+        then(just(()), move |()| events.lock().unwrap().push("cleanup"))
+    }
+
+    #[test]
+    fn test_finally() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sender = then(just(1), {
+            let events = events.clone();
+            move |value| {
+                events.lock().unwrap().push("sender");
+                value
+            }
+        });
+        let sender = finally(sender, record(&events));
+        assert_eq!(sync_wait(sender), Ok(Some(1)));
+        assert_eq!(*events.lock().unwrap(), ["sender", "cleanup"]);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sender = finally(just_error(TestError), record(&events));
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(TestError));
+        assert_eq!(*events.lock().unwrap(), ["cleanup"]);
+    }
+
+    #[test]
+    fn test_finally_stopped() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sender = finally(NeverSender::<i32>::new(), record(&events));
+        let result = sync_wait_for(sender, Duration::from_millis(10));
+        assert_eq!(result, Err(SyncWaitError::TimedOut));
+        assert_eq!(*events.lock().unwrap(), ["cleanup"]);
+    }
+
+    #[test]
+    fn test_finally_async_cleanup() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let cleanup = then(scheduler.schedule(), {
+            let events = events.clone();
+            move |()| events.lock().unwrap().push("cleanup")
+        });
+        let sender = finally(just(1), cleanup);
+        assert_eq!(sync_wait::<_, _, TestError>(sender), Ok(Some(1)));
+        assert_eq!(*events.lock().unwrap(), ["cleanup"]);
+
+        // A failing cleanup replaces the completion.
+        let sender = finally(just(1), just_error(TestError));
+        assert_eq!(sync_wait(sender), Err(TestError));
+
+        // A stopped one doesn't.
+        let cleanup = dematerialize(just(WaitResult::Stopped));
+        let sender = finally(just_error(TestError), cleanup);
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(TestError));
+    }
+}
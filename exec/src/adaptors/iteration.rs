@@ -0,0 +1,238 @@
+//! Building blocks of the adaptors running senders one after the other.
+
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopToken};
+use std::error::Error;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+pub enum Completion<V, E> {
+    Value(V),
+    Error(E),
+    Stopped,
+}
+
+impl<V, E> Completion<V, E> {
+    pub(crate) fn complete<R>(self, receiver: R)
+    where
+        R: SetValue<Value = V> + SetError<Error = E> + SetStopped,
+    {
+        match self {
+            Completion::Value(value) => receiver.set_value(value),
+            Completion::Error(error) => receiver.set_error(error),
+            Completion::Stopped => receiver.set_stopped(),
+        }
+    }
+}
+
+/// Receiver of one of the senders run in turn, it hands the completion back
+/// to the operation of the adaptor.
+///
+/// Type-erased so that the operation doesn't appear in the receiver type of
+/// the senders it stores. `M` stands for the state of the operation the
+/// completion touches, the receiver is only `Send` if that state is. The
+/// senders the completion connects can't be part of it, their type depends
+/// on the receiver's, adaptors require them to be `Send` instead.
+pub struct IterationReceiver<V, E, M> {
+    op: NonNull<()>,
+    complete: unsafe fn(NonNull<()>, Completion<V, E>),
+    stop_token: StopToken,
+    _marker: PhantomData<fn() -> M>,
+}
+
+// The operation of the adaptor outlives the senders it runs.
+unsafe impl<V: Send, E: Send, M: Send> Send for IterationReceiver<V, E, M> {}
+
+impl<V, E, M> IterationReceiver<V, E, M> {
+    /// # Safety
+    ///
+    /// `op` must stay valid until the receiver completes.
+    pub(crate) unsafe fn new(
+        op: NonNull<()>,
+        complete: unsafe fn(NonNull<()>, Completion<V, E>),
+        stop_token: StopToken,
+    ) -> Self {
+        Self {
+            op,
+            complete,
+            stop_token,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<V, E, M> SetValue for IterationReceiver<V, E, M> {
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        unsafe { (self.complete)(self.op, Completion::Value(value)) }
+    }
+}
+
+impl<V, E: Error, M> SetError for IterationReceiver<V, E, M> {
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        unsafe { (self.complete)(self.op, Completion::Error(error)) }
+    }
+}
+
+impl<V, E, M> SetStopped for IterationReceiver<V, E, M> {
+    fn set_stopped(self) {
+        unsafe { (self.complete)(self.op, Completion::Stopped) }
+    }
+}
+
+impl<V, E, M> GetStopToken for IterationReceiver<V, E, M> {
+    fn get_stop_token(&self) -> StopToken {
+        self.stop_token.clone()
+    }
+}
+
+/// Receiver of the schedule between two runs, `true` to run again and
+/// `false` when the scheduler stopped. `M` is the state of the operation,
+/// like for [`IterationReceiver`].
+pub struct StepReceiver<M> {
+    op: NonNull<()>,
+    step: unsafe fn(NonNull<()>, bool),
+    stop_token: StopToken,
+    _marker: PhantomData<fn() -> M>,
+}
+
+// The looping operation outlives its schedules.
+unsafe impl<M: Send> Send for StepReceiver<M> {}
+
+impl<M> StepReceiver<M> {
+    /// # Safety
+    ///
+    /// `op` must stay valid until the receiver completes.
+    pub(crate) unsafe fn new(
+        op: NonNull<()>,
+        step: unsafe fn(NonNull<()>, bool),
+        stop_token: StopToken,
+    ) -> Self {
+        Self {
+            op,
+            step,
+            stop_token,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<M> SetValue for StepReceiver<M> {
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        unsafe { (self.step)(self.op, true) }
+    }
+}
+
+impl<M> SetStopped for StepReceiver<M> {
+    fn set_stopped(self) {
+        unsafe { (self.step)(self.op, false) }
+    }
+}
+
+impl<M> GetStopToken for StepReceiver<M> {
+    fn get_stop_token(&self) -> StopToken {
+        self.stop_token.clone()
+    }
+}
+
+/// Decides whether a [`LoopOperation`] runs its sender again once a run
+/// completes, and what it waits on before doing so.
+pub trait LoopPolicy<V, E, R> {
+    /// Completes once the next run can start.
+    type Schedule: Sender<R>;
+
+    /// Called before the first run, which starts right away.
+    fn start(&mut self) {}
+
+    /// Returns the schedule of the next run, or the completion to forward to
+    /// end the loop.
+    fn next(&mut self, completion: Completion<V, E>) -> Result<Self::Schedule, Completion<V, E>>;
+}
+
+/// State of a [`LoopOperation`] its receivers touch, besides the factory.
+pub(crate) type LoopState<P, R> = (P, R);
+
+type ScheduleOperation<P, R> = <<P as LoopPolicy<
+    <R as SetValue>::Value,
+    <R as SetError>::Error,
+    StepReceiver<LoopState<P, R>>,
+>>::Schedule as Sender<StepReceiver<LoopState<P, R>>>>::Operation;
+
+/// Runs the sender made by `factory` over and over, as `policy` decides.
+///
+/// Each run is connected in the storage of the previous one, from the thread
+/// the previous one completed on, hence `factory` must be `Send`.
+pub struct LoopOperation<F, P, S, R>
+where
+    P: LoopPolicy<R::Value, R::Error, StepReceiver<LoopState<P, R>>>,
+    S: Sender<IterationReceiver<R::Value, R::Error, LoopState<P, R>>>,
+    R: SetValue + SetError,
+{
+    factory: F,
+    policy: P,
+    receiver: Option<R>,
+    schedule: Option<ScheduleOperation<P, R>>,
+    operation: Option<S::Operation>,
+}
+
+impl<F, P, S, R> LoopOperation<F, P, S, R>
+where
+    F: FnMut() -> S + Send,
+    P: LoopPolicy<R::Value, R::Error, StepReceiver<LoopState<P, R>>>,
+    S: Sender<IterationReceiver<R::Value, R::Error, LoopState<P, R>>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    pub(crate) fn new(factory: F, policy: P, receiver: R) -> Self {
+        Self {
+            factory,
+            policy,
+            receiver: Some(receiver),
+            schedule: None,
+            operation: None,
+        }
+    }
+
+    unsafe fn step(op: NonNull<()>, run: bool) {
+        let this = &mut *op.cast::<Self>().as_ptr();
+        let stop_token = this.receiver.as_ref().unwrap().get_stop_token();
+        if !run || stop_token.stop_requested() {
+            return this.receiver.take().unwrap().set_stopped();
+        }
+
+        let receiver = IterationReceiver::new(op, Self::complete, stop_token);
+        // Replacing the previous run, which has completed.
+        this.operation
+            .insert((this.factory)().connect(receiver))
+            .start();
+    }
+
+    unsafe fn complete(op: NonNull<()>, completion: Completion<R::Value, R::Error>) {
+        let this = &mut *op.cast::<Self>().as_ptr();
+        match this.policy.next(completion) {
+            Ok(schedule) => {
+                let stop_token = this.receiver.as_ref().unwrap().get_stop_token();
+                let receiver = StepReceiver::new(op, Self::step, stop_token);
+                this.schedule.insert(schedule.connect(receiver)).start();
+            }
+            Err(completion) => completion.complete(this.receiver.take().unwrap()),
+        }
+    }
+}
+
+impl<F, P, S, R> OperationState for LoopOperation<F, P, S, R>
+where
+    F: FnMut() -> S + Send,
+    P: LoopPolicy<R::Value, R::Error, StepReceiver<LoopState<P, R>>>,
+    S: Sender<IterationReceiver<R::Value, R::Error, LoopState<P, R>>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    fn start(&mut self) {
+        self.policy.start();
+        unsafe { Self::step(NonNull::from(&mut *self).cast(), true) };
+    }
+}
@@ -0,0 +1,183 @@
+use crate::Error;
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopToken};
+
+use std::marker::PhantomData;
+
+/// Transforms the error of `sender` with `func`, values and stopped
+/// completions pass through.
+pub fn map_error<S, F, E>(sender: S, func: F) -> MapError<S, F, E> {
+    MapError::new(sender, func)
+}
+
+/// Widens the error of `sender` into an [`Error`].
+///
+/// The error type is inferred from the sender, a sender that never completes
+/// with an error needs it spelled out, e.g. `box_error::<_, Infallible>`.
+pub fn box_error<S, E>(sender: S) -> MapError<S, fn(E) -> Error, E>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    MapError::new(sender, Error::new)
+}
+
+pub struct MapError<S, F, E> {
+    sender: S,
+    func: F,
+    _phantom: PhantomData<E>,
+}
+
+impl<S, F, E> MapError<S, F, E> {
+    pub fn new(sender: S, func: F) -> Self {
+        Self {
+            sender,
+            func,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+pub struct MapErrorReceiver<F, R, E> {
+    func: F,
+    receiver: R,
+    _phantom: PhantomData<E>,
+}
+
+impl<F, R, E> SetValue for MapErrorReceiver<F, R, E>
+where
+    R: SetValue,
+{
+    type Value = R::Value;
+
+    fn set_value(self, value: Self::Value) {
+        self.receiver.set_value(value);
+    }
+}
+
+impl<F, R, E, O> SetError for MapErrorReceiver<F, R, E>
+where
+    F: FnOnce(E) -> O,
+    R: SetError<Error = O>,
+    E: std::error::Error,
+{
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_error((self.func)(error));
+    }
+}
+
+impl<F, R, E> SetStopped for MapErrorReceiver<F, R, E>
+where
+    R: SetStopped,
+{
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<F, R, E> GetStopToken for MapErrorReceiver<F, R, E>
+where
+    R: GetStopToken,
+{
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<F, R, E> GetScheduler for MapErrorReceiver<F, R, E>
+where
+    R: GetScheduler,
+{
+    type Scheduler = R::Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        self.receiver.get_scheduler()
+    }
+}
+
+pub struct MapErrorOperation<O> {
+    operation: O,
+}
+
+impl<O> OperationState for MapErrorOperation<O>
+where
+    O: OperationState,
+{
+    fn start(&mut self) {
+        self.operation.start()
+    }
+}
+
+impl<S, F, E, O, R> Sender<R> for MapError<S, F, E>
+where
+    S: Sender<MapErrorReceiver<F, R, E>>,
+    F: FnOnce(E) -> O + Send + 'static,
+    R: SetError<Error = O>,
+{
+    type Value = S::Value;
+    type Error = O;
+
+    type Operation = MapErrorOperation<S::Operation>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        MapErrorOperation {
+            operation: self.sender.connect(MapErrorReceiver {
+                func: self.func,
+                receiver,
+                _phantom: PhantomData,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::{catch_panic, then, then_try};
+    use crate::consumers::sync_wait;
+    use crate::factories::{just, just_error};
+    use exec_core::panic::PanicError;
+    use exec_test::errors::TestError;
+    use std::fmt;
+
+    #[derive(Debug, PartialEq)]
+    struct OtherError;
+
+    impl fmt::Display for OtherError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("OtherError")
+        }
+    }
+
+    impl std::error::Error for OtherError {}
+
+    #[test]
+    fn test_map_error() {
+        let sender = map_error(just_error(TestError), |_: TestError| OtherError);
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(OtherError));
+
+        let sender = map_error(just(1), |_: TestError| OtherError);
+        assert_eq!(sync_wait(sender), Ok(Some(1)));
+    }
+
+    #[test]
+    fn test_box_error() {
+        // Stages with different error types share one error channel.
+        let sender = box_error(just_error(TestError));
+        let sender = then_try(sender, |_: ()| Err::<(), _>(Error::new(OtherError)));
+        let error = sync_wait(sender).unwrap_err();
+        assert!(error.is::<TestError>());
+
+        let sender = then_try(just(1), |_| Err::<i32, _>(OtherError));
+        let sender = box_error::<_, OtherError>(sender);
+        let sender = then_try(sender, |_| Err::<(), _>(Error::new(TestError)));
+        let error = sync_wait(sender).unwrap_err();
+        assert!(error.is::<OtherError>());
+
+        let sender = then(just(1), |_: i32| -> i32 { panic!("boom") });
+        let sender = box_error::<_, PanicError>(catch_panic(sender));
+        let error = sync_wait(sender).unwrap_err();
+        assert_eq!(error.to_string(), "panicked: boom");
+    }
+}
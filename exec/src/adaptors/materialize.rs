@@ -0,0 +1,273 @@
+use crate::consumers::WaitResult;
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopToken};
+use std::error::Error;
+use std::marker::PhantomData;
+
+/// Completes with the completion of `sender` as a [`WaitResult`] value, be
+/// it a value, an error or stopped.
+///
+/// The completion can then be logged, routed or stored like any other value,
+/// and replayed with [`dematerialize`].
+pub fn materialize<S>(sender: S) -> Materialize<S> {
+    Materialize::new(sender)
+}
+
+/// Completes the receiver with the [`WaitResult`] value `sender` completes
+/// with, the inverse of [`materialize`].
+pub fn dematerialize<S>(sender: S) -> Dematerialize<S> {
+    Dematerialize::new(sender)
+}
+
+pub struct Materialize<S> {
+    sender: S,
+}
+
+impl<S> Materialize<S> {
+    pub fn new(sender: S) -> Self {
+        Self { sender }
+    }
+}
+
+pub struct MaterializeReceiver<R, V, E> {
+    receiver: R,
+    _phantom: PhantomData<fn(V, E)>,
+}
+
+impl<R, V, E> SetValue for MaterializeReceiver<R, V, E>
+where
+    R: SetValue<Value = WaitResult<V, E>>,
+{
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        self.receiver.set_value(WaitResult::Value(value));
+    }
+}
+
+impl<R, V, E> SetError for MaterializeReceiver<R, V, E>
+where
+    R: SetValue<Value = WaitResult<V, E>>,
+    E: Error,
+{
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_value(WaitResult::Error(error));
+    }
+}
+
+impl<R, V, E> SetStopped for MaterializeReceiver<R, V, E>
+where
+    R: SetValue<Value = WaitResult<V, E>>,
+{
+    fn set_stopped(self) {
+        self.receiver.set_value(WaitResult::Stopped);
+    }
+}
+
+impl<R, V, E> GetStopToken for MaterializeReceiver<R, V, E>
+where
+    R: GetStopToken,
+{
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<R, V, E> GetScheduler for MaterializeReceiver<R, V, E>
+where
+    R: GetScheduler,
+{
+    type Scheduler = R::Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        self.receiver.get_scheduler()
+    }
+}
+
+pub struct MaterializeOperation<O> {
+    operation: O,
+}
+
+impl<O> OperationState for MaterializeOperation<O>
+where
+    O: OperationState,
+{
+    fn start(&mut self) {
+        self.operation.start()
+    }
+}
+
+impl<S, R, V, E> Sender<R> for Materialize<S>
+where
+    S: Sender<MaterializeReceiver<R, V, E>, Value = V, Error = E>,
+    R: SetValue<Value = WaitResult<V, E>>,
+{
+    type Value = WaitResult<V, E>;
+    type Error = ();
+
+    type Operation = MaterializeOperation<S::Operation>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        MaterializeOperation {
+            operation: self.sender.connect(MaterializeReceiver {
+                receiver,
+                _phantom: PhantomData,
+            }),
+        }
+    }
+}
+
+pub struct Dematerialize<S> {
+    sender: S,
+}
+
+impl<S> Dematerialize<S> {
+    pub fn new(sender: S) -> Self {
+        Self { sender }
+    }
+}
+
+pub struct DematerializeReceiver<R> {
+    receiver: R,
+}
+
+impl<R, V, E> SetValue for DematerializeReceiver<R>
+where
+    R: SetValue<Value = V> + SetError<Error = E> + SetStopped,
+{
+    type Value = WaitResult<V, E>;
+
+    fn set_value(self, value: Self::Value) {
+        value.complete(self.receiver);
+    }
+}
+
+impl<R> SetError for DematerializeReceiver<R>
+where
+    R: SetError,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_error(error);
+    }
+}
+
+impl<R> SetStopped for DematerializeReceiver<R>
+where
+    R: SetStopped,
+{
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<R> GetStopToken for DematerializeReceiver<R>
+where
+    R: GetStopToken,
+{
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<R> GetScheduler for DematerializeReceiver<R>
+where
+    R: GetScheduler,
+{
+    type Scheduler = R::Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        self.receiver.get_scheduler()
+    }
+}
+
+pub struct DematerializeOperation<O> {
+    operation: O,
+}
+
+impl<O> OperationState for DematerializeOperation<O>
+where
+    O: OperationState,
+{
+    fn start(&mut self) {
+        self.operation.start()
+    }
+}
+
+impl<S, R, V, E> Sender<R> for Dematerialize<S>
+where
+    S: Sender<DematerializeReceiver<R>, Value = WaitResult<V, E>>,
+    R: SetValue<Value = V> + SetError<Error = E> + SetStopped,
+{
+    type Value = V;
+    type Error = E;
+
+    type Operation = DematerializeOperation<S::Operation>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        DematerializeOperation {
+            operation: self.sender.connect(DematerializeReceiver { receiver }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then;
+    use crate::consumers::sync_wait;
+    use crate::factories::{just, just_error};
+    use exec_test::errors::TestError;
+    use exec_test::receivers::{ChannelReceiver, Completed};
+    use exec_test::senders::NeverSender;
+
+    #[test]
+    fn test_materialize() {
+        let result = sync_wait(materialize(just(42)));
+        assert_eq!(result, Ok(Some(WaitResult::<_, ()>::Value(42))));
+
+        let result = sync_wait(materialize(just_error(TestError)));
+        assert_eq!(result, Ok(Some(WaitResult::<(), _>::Error(TestError))));
+    }
+
+    #[test]
+    fn test_materialize_stopped() {
+        let (receiver, completed) = ChannelReceiver::<WaitResult<i32, ()>, ()>::new();
+        let stop_source = receiver.stop_source();
+        let mut op = materialize(NeverSender::<i32>::new()).connect(receiver);
+        op.start();
+        stop_source.request_stop();
+        assert_eq!(
+            completed.recv().unwrap(),
+            Completed::Value(WaitResult::Stopped)
+        );
+    }
+
+    #[test]
+    fn test_dematerialize() {
+        let sender = dematerialize(materialize(just_error(TestError)));
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(TestError));
+
+        // Completions stored as values are replayed later on.
+        let stored = vec![
+            WaitResult::Value(1),
+            WaitResult::Error(TestError),
+            WaitResult::Stopped,
+        ];
+        let replayed: Vec<_> = stored
+            .into_iter()
+            .map(|result| sync_wait(dematerialize(just(result))))
+            .collect();
+        assert_eq!(replayed, vec![Ok(Some(1)), Err(TestError), Ok(None)]);
+
+        // Errors are recovered from like any other value.
+        let sender = then(materialize(just_error(TestError)), |result| match result {
+            WaitResult::Error(_) => WaitResult::Value(()),
+            result => result,
+        });
+        assert_eq!(sync_wait(dematerialize(sender)), Ok(Some(())));
+    }
+}
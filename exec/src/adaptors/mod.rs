@@ -1,3 +1,25 @@
+mod catch_panic;
+mod finally;
+mod iteration;
+mod map_error;
+mod materialize;
+mod repeat_effect_until;
+mod retry;
+mod retry_with_backoff;
 mod then;
+mod then_try;
+mod timeout;
+mod when_any;
 
+pub use catch_panic::{catch_panic, CatchPanic};
+pub use finally::{finally, Finally};
+pub use iteration::{IterationReceiver, StepReceiver};
+pub use map_error::{box_error, map_error, MapError};
+pub use materialize::{dematerialize, materialize, Dematerialize, Materialize};
+pub use repeat_effect_until::{repeat_effect_until, RepeatEffectUntil};
+pub use retry::{retry, Retry, RetryPolicy};
+pub use retry_with_backoff::{retry_with_backoff, Backoff, RetryWithBackoff};
 pub use then::{then, Then};
+pub use then_try::{then_try, ThenTry};
+pub use timeout::{timeout, TimedOut, Timeout};
+pub use when_any::{when_any, WhenAny};
@@ -0,0 +1,128 @@
+use super::iteration::{Completion, IterationReceiver, LoopOperation, LoopPolicy, LoopState};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{Scheduler, Sender};
+use exec_executor::{ScheduleTrampoline, TrampolineScheduler};
+
+/// Runs the sender made by `factory` over and over until `predicate` accepts
+/// its value, which the returned sender then completes with.
+///
+/// Each run is connected in the storage of the previous one, on the thread
+/// it completed on, hence `factory` must be `Send`. Runs after the first are
+/// started through a [`TrampolineScheduler`], so senders completing inline
+/// don't grow the stack. Errors and stopped completions end the loop, as
+/// does stop requested through the receiver's stop token.
+pub fn repeat_effect_until<F, S, P, V>(factory: F, predicate: P) -> RepeatEffectUntil<F, P>
+where
+    F: FnMut() -> S,
+    P: FnMut(&V) -> bool,
+{
+    RepeatEffectUntil::new(factory, predicate)
+}
+
+pub struct RepeatEffectUntil<F, P> {
+    factory: F,
+    predicate: P,
+}
+
+impl<F, P> RepeatEffectUntil<F, P> {
+    pub fn new(factory: F, predicate: P) -> Self {
+        Self { factory, predicate }
+    }
+}
+
+/// Runs again through a trampoline until the predicate accepts a value.
+pub struct RepeatUntilLoop<P> {
+    predicate: P,
+    trampoline: TrampolineScheduler,
+}
+
+impl<P, V, E, R> LoopPolicy<V, E, R> for RepeatUntilLoop<P>
+where
+    P: FnMut(&V) -> bool,
+    R: SetValue<Value = ()> + SetStopped,
+{
+    type Schedule = ScheduleTrampoline<R>;
+
+    fn next(&mut self, completion: Completion<V, E>) -> Result<Self::Schedule, Completion<V, E>> {
+        match completion {
+            Completion::Value(value) if !(self.predicate)(&value) => Ok(self.trampoline.schedule()),
+            completion => Err(completion),
+        }
+    }
+}
+
+impl<F, P, S, R> Sender<R> for RepeatEffectUntil<F, P>
+where
+    F: FnMut() -> S + Send,
+    P: FnMut(&R::Value) -> bool,
+    S: Sender<IterationReceiver<R::Value, R::Error, LoopState<RepeatUntilLoop<P>, R>>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    type Value = R::Value;
+    type Error = R::Error;
+
+    type Operation = LoopOperation<F, RepeatUntilLoop<P>, S, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let predicate = RepeatUntilLoop {
+            predicate: self.predicate,
+            trampoline: TrampolineScheduler::default(),
+        };
+        LoopOperation::new(self.factory, predicate, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then;
+    use crate::consumers::{sync_wait, sync_wait_for, SyncWaitError};
+    use crate::factories::{just, just_error};
+    use exec_core::Scheduler;
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    #[test]
+    fn test_repeat_effect_until() {
+        // Inline runs don't grow the stack.
+        let mut count = 0;
+        let sender = repeat_effect_until(
+            || {
+                count += 1;
+                just(count)
+            },
+            |count| *count == 100_000,
+        );
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(100_000)));
+
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+        let mut count = 0;
+        let sender = repeat_effect_until(
+            move || {
+                count += 1;
+                let count = count;
+                then(scheduler.schedule(), move |()| count)
+            },
+            |count| *count == 3,
+        );
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_repeat_effect_until_error() {
+        let sender = repeat_effect_until(|| just_error(TestError), |_: &()| false);
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+
+    #[test]
+    fn test_repeat_effect_until_stopped() {
+        let context = SingleThreadContext::new();
+        let scheduler = context.get_scheduler();
+        let sender = repeat_effect_until(move || scheduler.clone().schedule(), |_| false);
+        let result = sync_wait_for::<_, _, Infallible>(sender, Duration::from_millis(10));
+        assert_eq!(result, Err(SyncWaitError::TimedOut));
+    }
+}
@@ -0,0 +1,132 @@
+use super::iteration::{Completion, IterationReceiver, LoopOperation, LoopPolicy, LoopState};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{Scheduler, Sender};
+use exec_executor::{ScheduleTrampoline, TrampolineScheduler};
+
+/// Decides whether a failed sender runs again.
+pub trait RetryPolicy<E> {
+    fn retry(&mut self, error: &E) -> bool;
+}
+
+/// Retries up to that many times, whatever the error.
+impl<E> RetryPolicy<E> for usize {
+    fn retry(&mut self, _error: &E) -> bool {
+        let retry = *self > 0;
+        *self = self.saturating_sub(1);
+        retry
+    }
+}
+
+/// Runs the sender made by `factory` again each time it completes with an
+/// error, as long as `policy` agrees. The last error is forwarded once the
+/// policy gives up.
+///
+/// Like [`repeat_effect_until`](super::repeat_effect_until), runs share the
+/// same storage and runs after the first are started through a
+/// [`TrampolineScheduler`].
+pub fn retry<F, S, P>(factory: F, policy: P) -> Retry<F, P>
+where
+    F: FnMut() -> S,
+{
+    Retry::new(factory, policy)
+}
+
+pub struct Retry<F, P> {
+    factory: F,
+    policy: P,
+}
+
+impl<F, P> Retry<F, P> {
+    pub fn new(factory: F, policy: P) -> Self {
+        Self { factory, policy }
+    }
+}
+
+/// Runs again through a trampoline each time the policy agrees to retry.
+pub struct RetryLoop<P> {
+    policy: P,
+    trampoline: TrampolineScheduler,
+}
+
+impl<P, V, E, R> LoopPolicy<V, E, R> for RetryLoop<P>
+where
+    P: RetryPolicy<E>,
+    R: SetValue<Value = ()> + SetStopped,
+{
+    type Schedule = ScheduleTrampoline<R>;
+
+    fn next(&mut self, completion: Completion<V, E>) -> Result<Self::Schedule, Completion<V, E>> {
+        match completion {
+            Completion::Error(error) if self.policy.retry(&error) => Ok(self.trampoline.schedule()),
+            completion => Err(completion),
+        }
+    }
+}
+
+impl<F, P, S, R> Sender<R> for Retry<F, P>
+where
+    F: FnMut() -> S + Send,
+    P: RetryPolicy<R::Error>,
+    S: Sender<IterationReceiver<R::Value, R::Error, LoopState<RetryLoop<P>, R>>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    type Value = R::Value;
+    type Error = R::Error;
+
+    type Operation = LoopOperation<F, RetryLoop<P>, S, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let policy = RetryLoop {
+            policy: self.policy,
+            trampoline: TrampolineScheduler::default(),
+        };
+        LoopOperation::new(self.factory, policy, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then_try;
+    use crate::consumers::sync_wait;
+    use crate::factories::just;
+    use exec_test::errors::TestError;
+
+    #[test]
+    fn test_retry() {
+        let mut attempts = 0;
+        let sender = retry(
+            || {
+                attempts += 1;
+                let attempt = attempts;
+                then_try(just(attempt), |attempt| {
+                    if attempt < 3 {
+                        Err(TestError)
+                    } else {
+                        Ok(attempt)
+                    }
+                })
+            },
+            5,
+        );
+        assert_eq!(sync_wait::<_, _, TestError>(sender), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let mut attempts = 0;
+        let sender = retry(
+            || {
+                attempts += 1;
+                then_try(just(()), |()| Err::<(), _>(TestError))
+            },
+            2,
+        );
+        assert_eq!(sync_wait::<_, (), TestError>(sender), Err(TestError));
+        assert_eq!(attempts, 3);
+
+        // Inline failures don't grow the stack.
+        let sender = retry(|| then_try(just(()), |()| Err::<(), _>(TestError)), 100_000);
+        assert_eq!(sync_wait::<_, (), TestError>(sender), Err(TestError));
+    }
+}
@@ -0,0 +1,394 @@
+use super::iteration::{
+    Completion, IterationReceiver, LoopOperation, LoopPolicy, LoopState, StepReceiver,
+};
+use super::RetryPolicy;
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{Sender, TimedScheduler};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// When and how often [`retry_with_backoff`] runs a failed sender again.
+///
+/// Attempts are spaced by a delay growing exponentially from
+/// `initial_delay`, capped at `max_delay`. Defaults to 3 attempts, a 100ms
+/// initial delay doubling each time up to 10s, no jitter, no time limit and
+/// every error retried.
+///
+/// As a [`RetryPolicy`], it counts the attempts made so far, so that
+/// [`retry`](super::retry) gives up along the same rules without waiting.
+#[derive(Clone, Debug)]
+pub struct Backoff<P> {
+    /// Attempts made so far.
+    attempts: u32,
+    max_attempts: u32,
+    initial_delay: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    jitter: f64,
+    max_elapsed: Option<Duration>,
+    predicate: P,
+}
+
+impl<E> Backoff<fn(&E) -> bool> {
+    pub fn new() -> Self {
+        Self {
+            attempts: 1,
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_delay: Duration::from_secs(10),
+            jitter: 0.0,
+            max_elapsed: None,
+            predicate: |_| true,
+        }
+    }
+}
+
+impl<E> Default for Backoff<fn(&E) -> bool> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Backoff<P> {
+    /// Gives up after that many attempts, the first one included.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is 0.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be positive");
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// The factor the delay grows by after each attempt.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        assert!(multiplier >= 1.0, "multiplier must be at least 1");
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Shortens each delay by a random part of up to `jitter` of it, between
+    /// 0 for none and 1 for the whole delay. Spreads out the retries of
+    /// clients failing at the same time.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        assert!((0.0..=1.0).contains(&jitter), "jitter must be within 0..=1");
+        self.jitter = jitter;
+        self
+    }
+
+    /// Gives up rather than waiting for an attempt that would start more
+    /// than `max_elapsed` after the first one.
+    pub fn with_max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = Some(max_elapsed);
+        self
+    }
+
+    /// Only retries the errors `predicate` accepts, the others are forwarded
+    /// right away.
+    pub fn retry_if<Q, E>(self, predicate: Q) -> Backoff<Q>
+    where
+        Q: FnMut(&E) -> bool,
+    {
+        Backoff {
+            attempts: self.attempts,
+            max_attempts: self.max_attempts,
+            initial_delay: self.initial_delay,
+            multiplier: self.multiplier,
+            max_delay: self.max_delay,
+            jitter: self.jitter,
+            max_elapsed: self.max_elapsed,
+            predicate,
+        }
+    }
+
+    /// The delay before the attempt following `attempt`, counted from 1.
+    /// `random` is within `0..1`.
+    fn delay(&self, attempt: u32, random: f64) -> Duration {
+        let exponent = i32::try_from(attempt - 1).unwrap_or(i32::MAX);
+        let factor = self.multiplier.powi(exponent).min(f64::MAX);
+        // Capped in seconds, the uncapped delay soon overflows `Duration`.
+        let secs = self.initial_delay.as_secs_f64() * factor;
+        let delay = Duration::from_secs_f64(secs.min(self.max_delay.as_secs_f64()));
+        delay.mul_f64(1.0 - self.jitter * random)
+    }
+}
+
+/// Like [`retry`](super::retry), waiting on `scheduler` for the delay
+/// `backoff` gives between attempts.
+///
+/// The first attempt starts right away. The last error is forwarded once
+/// `backoff` gives up, and stop requested while waiting completes with
+/// stopped.
+pub fn retry_with_backoff<F, S, Sch, P>(
+    factory: F,
+    scheduler: Sch,
+    backoff: Backoff<P>,
+) -> RetryWithBackoff<F, Sch, P>
+where
+    F: FnMut() -> S,
+{
+    RetryWithBackoff::new(factory, scheduler, backoff)
+}
+
+pub struct RetryWithBackoff<F, Sch, P> {
+    factory: F,
+    scheduler: Sch,
+    backoff: Backoff<P>,
+}
+
+impl<F, Sch, P> RetryWithBackoff<F, Sch, P> {
+    pub fn new(factory: F, scheduler: Sch, backoff: Backoff<P>) -> Self {
+        Self {
+            factory,
+            scheduler,
+            backoff,
+        }
+    }
+}
+
+/// Retries the errors the predicate accepts until `max_attempts` attempts
+/// have been made.
+impl<P, E> RetryPolicy<E> for Backoff<P>
+where
+    P: FnMut(&E) -> bool,
+{
+    fn retry(&mut self, error: &E) -> bool {
+        if self.attempts >= self.max_attempts || !(self.predicate)(error) {
+            return false;
+        }
+        self.attempts += 1;
+        true
+    }
+}
+
+/// Small xorshift generator for the jitter, seeded per operation.
+struct Jitter(u64);
+
+impl Jitter {
+    fn new() -> Self {
+        Self(RandomState::new().build_hasher().finish() | 1)
+    }
+
+    /// A number within `0..1`.
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Runs again on `scheduler` once the delay `backoff` gives has elapsed.
+pub struct BackoffLoop<Sch, P> {
+    scheduler: Sch,
+    backoff: Backoff<P>,
+    /// When the first attempt started.
+    started: Option<Instant>,
+    jitter: Jitter,
+}
+
+impl<Sch, P, V, E, R> LoopPolicy<V, E, R> for BackoffLoop<Sch, P>
+where
+    Sch: TimedScheduler<R>,
+    P: FnMut(&E) -> bool,
+{
+    type Schedule = Sch::TimedSender;
+
+    fn start(&mut self) {
+        self.started = Some(self.scheduler.now());
+    }
+
+    fn next(&mut self, completion: Completion<V, E>) -> Result<Self::Schedule, Completion<V, E>> {
+        let Completion::Error(error) = &completion else {
+            return Err(completion);
+        };
+        let attempt = self.backoff.attempts;
+        if !self.backoff.retry(error) {
+            return Err(completion);
+        }
+
+        let delay = self.backoff.delay(attempt, self.jitter.next());
+        let deadline = self.scheduler.now() + delay;
+        if let (Some(max_elapsed), Some(started)) = (self.backoff.max_elapsed, self.started) {
+            if deadline > started + max_elapsed {
+                return Err(completion);
+            }
+        }
+        Ok(self.scheduler.schedule_at(deadline))
+    }
+}
+
+impl<F, Sch, P, S, R> Sender<R> for RetryWithBackoff<F, Sch, P>
+where
+    F: FnMut() -> S + Send,
+    Sch: TimedScheduler<StepReceiver<LoopState<BackoffLoop<Sch, P>, R>>>,
+    P: FnMut(&R::Error) -> bool,
+    S: Sender<IterationReceiver<R::Value, R::Error, LoopState<BackoffLoop<Sch, P>, R>>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    type Value = R::Value;
+    type Error = R::Error;
+
+    type Operation = LoopOperation<F, BackoffLoop<Sch, P>, S, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let policy = BackoffLoop {
+            scheduler: self.scheduler,
+            backoff: self.backoff,
+            started: None,
+            jitter: Jitter::new(),
+        };
+        LoopOperation::new(self.factory, policy, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::{retry, then_try};
+    use crate::consumers::{sync_wait, sync_wait_for, SyncWaitError};
+    use crate::factories::{just, just_error};
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = Backoff::<fn(&()) -> bool>::new()
+            .with_initial_delay(Duration::from_millis(10))
+            .with_multiplier(3.0)
+            .with_max_delay(Duration::from_millis(50));
+        let delays: Vec<_> = (1..=4).map(|attempt| backoff.delay(attempt, 0.5)).collect();
+        assert_eq!(delays, [10, 30, 50, 50].map(Duration::from_millis),);
+
+        let backoff = backoff.with_jitter(0.5);
+        assert_eq!(backoff.delay(1, 0.0), Duration::from_millis(10));
+        assert_eq!(backoff.delay(1, 0.5), Duration::from_micros(7500));
+
+        // Attempts far past the cap don't overflow.
+        let backoff = Backoff::<fn(&()) -> bool>::new().with_max_attempts(100);
+        assert_eq!(backoff.delay(100, 0.0), Duration::from_secs(10));
+        assert_eq!(backoff.delay(u32::MAX, 0.0), Duration::from_secs(10));
+        let backoff = backoff.with_initial_delay(Duration::ZERO);
+        assert_eq!(backoff.delay(u32::MAX, 0.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_retry_with_backoff() {
+        let context = SingleThreadContext::new();
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let sender = retry_with_backoff(
+            {
+                let attempts = attempts.clone();
+                move || {
+                    let mut attempts = attempts.lock().unwrap();
+                    attempts.push(Instant::now());
+                    let attempt = attempts.len();
+                    then_try(just(attempt), |attempt| {
+                        if attempt < 3 {
+                            Err(TestError)
+                        } else {
+                            Ok(attempt)
+                        }
+                    })
+                }
+            },
+            context.get_scheduler(),
+            Backoff::new()
+                .with_initial_delay(Duration::from_millis(5))
+                .with_max_attempts(5),
+        );
+        assert_eq!(sync_wait::<_, _, TestError>(sender), Ok(Some(3)));
+
+        let attempts = attempts.lock().unwrap();
+        assert!(attempts[1] - attempts[0] >= Duration::from_millis(5));
+        assert!(attempts[2] - attempts[1] >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_retry_with_backoff_gives_up() {
+        let context = SingleThreadContext::new();
+        let backoff = Backoff::new().with_initial_delay(Duration::from_millis(1));
+
+        let mut attempts = 0;
+        let sender = retry_with_backoff(
+            || {
+                attempts += 1;
+                just_error(TestError)
+            },
+            context.get_scheduler(),
+            backoff.clone().with_max_attempts(2),
+        );
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(TestError));
+        assert_eq!(attempts, 2);
+
+        // Errors the predicate rejects aren't retried.
+        let mut attempts = 0;
+        let sender = retry_with_backoff(
+            || {
+                attempts += 1;
+                just_error(TestError)
+            },
+            context.get_scheduler(),
+            backoff.clone().retry_if(|_: &TestError| false),
+        );
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(TestError));
+        assert_eq!(attempts, 1);
+
+        // Neither are attempts past the time limit.
+        let mut attempts = 0;
+        let sender = retry_with_backoff(
+            || {
+                attempts += 1;
+                just_error(TestError)
+            },
+            context.get_scheduler(),
+            backoff
+                .with_max_attempts(10)
+                .with_initial_delay(Duration::from_millis(20))
+                .with_max_elapsed(Duration::from_millis(30)),
+        );
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(TestError));
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_backoff_retry_policy() {
+        // Same rules with `retry`, without waiting.
+        let mut attempts = 0;
+        let sender = retry(
+            || {
+                attempts += 1;
+                just_error(TestError)
+            },
+            Backoff::new().with_initial_delay(Duration::from_secs(60)),
+        );
+        assert_eq!(sync_wait::<_, (), _>(sender), Err(TestError));
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn test_retry_with_backoff_stopped() {
+        let context = SingleThreadContext::new();
+        let sender = retry_with_backoff(
+            || just_error(TestError),
+            context.get_scheduler(),
+            Backoff::new().with_initial_delay(Duration::from_secs(60)),
+        );
+        let result = sync_wait_for::<_, (), _>(sender, Duration::from_millis(10));
+        assert_eq!(result, Err(SyncWaitError::TimedOut));
+    }
+}
@@ -1,5 +1,5 @@
-use exec_core::receiver::SetValue;
-use exec_core::{OperationState, Sender};
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopToken};
 
 use std::marker::PhantomData;
 
@@ -41,6 +41,46 @@ where
     }
 }
 
+impl<F, R, I> SetError for ThenReceiver<F, R, I>
+where
+    R: SetError,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_error(error);
+    }
+}
+
+impl<F, R, I> SetStopped for ThenReceiver<F, R, I>
+where
+    R: SetStopped,
+{
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<F, R, I> GetStopToken for ThenReceiver<F, R, I>
+where
+    R: GetStopToken,
+{
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<F, R, I> GetScheduler for ThenReceiver<F, R, I>
+where
+    R: GetScheduler,
+{
+    type Scheduler = R::Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        self.receiver.get_scheduler()
+    }
+}
+
 pub struct ThenOperation<O> {
     operation: O,
 }
@@ -61,7 +101,7 @@ where
     R: SetValue<Value = O>,
 {
     type Value = R::Value;
-    type Error = ();
+    type Error = S::Error;
 
     type Operation = ThenOperation<S::Operation>;
 
@@ -0,0 +1,152 @@
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopToken};
+
+use std::marker::PhantomData;
+
+/// Like [`then`](crate::then) for a fallible `func`: `Ok` completes the value
+/// channel and `Err` the error channel.
+///
+/// The error of `func` converts into the error type of the downstream
+/// receiver, which is also the one upstream errors are forwarded to. For an
+/// [`Error`](crate::Error) downstream, an error without a `From` conversion is
+/// wrapped with [`Error::new`](crate::Error::new) in `func`.
+pub fn then_try<S, F, I>(sender: S, func: F) -> ThenTry<S, F, I> {
+    ThenTry::new(sender, func)
+}
+
+pub struct ThenTry<S, F, I> {
+    sender: S,
+    func: F,
+    _phantom: PhantomData<I>,
+}
+
+impl<S, F, I> ThenTry<S, F, I> {
+    pub fn new(sender: S, func: F) -> Self {
+        Self {
+            sender,
+            func,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+pub struct ThenTryReceiver<F, R, I> {
+    func: F,
+    receiver: R,
+    _phantom: PhantomData<I>,
+}
+
+impl<F, R, I, O, E> SetValue for ThenTryReceiver<F, R, I>
+where
+    F: FnOnce(I) -> Result<O, E>,
+    R: SetValue<Value = O> + SetError,
+    E: Into<R::Error>,
+{
+    type Value = I;
+
+    fn set_value(self, value: Self::Value) {
+        match (self.func)(value) {
+            Ok(value) => self.receiver.set_value(value),
+            Err(error) => self.receiver.set_error(error.into()),
+        }
+    }
+}
+
+impl<F, R, I> SetError for ThenTryReceiver<F, R, I>
+where
+    R: SetError,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_error(error);
+    }
+}
+
+impl<F, R, I> SetStopped for ThenTryReceiver<F, R, I>
+where
+    R: SetStopped,
+{
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<F, R, I> GetStopToken for ThenTryReceiver<F, R, I>
+where
+    R: GetStopToken,
+{
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<F, R, I> GetScheduler for ThenTryReceiver<F, R, I>
+where
+    R: GetScheduler,
+{
+    type Scheduler = R::Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        self.receiver.get_scheduler()
+    }
+}
+
+pub struct ThenTryOperation<O> {
+    operation: O,
+}
+
+impl<O> OperationState for ThenTryOperation<O>
+where
+    O: OperationState,
+{
+    fn start(&mut self) {
+        self.operation.start()
+    }
+}
+
+impl<S, F, I, O, E, R> Sender<R> for ThenTry<S, F, I>
+where
+    S: Sender<ThenTryReceiver<F, R, I>>,
+    F: FnOnce(I) -> Result<O, E> + Send + 'static,
+    R: SetValue<Value = O> + SetError,
+    E: Into<R::Error>,
+{
+    type Value = O;
+    type Error = E;
+
+    type Operation = ThenTryOperation<S::Operation>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        ThenTryOperation {
+            operation: self.sender.connect(ThenTryReceiver {
+                func: self.func,
+                receiver,
+                _phantom: PhantomData,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumers::sync_wait;
+    use crate::factories::{just, just_error};
+    use exec_test::errors::TestError;
+
+    #[test]
+    fn test_then_try() {
+        let sender = then_try(just(42), |x| Ok::<_, TestError>(x + 1));
+        assert_eq!(sync_wait(sender), Ok(Some(43)));
+
+        let sender = then_try(just(42), |_| Err::<i32, _>(TestError));
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+
+    #[test]
+    fn test_then_try_forwards_upstream_error() {
+        let sender = then_try(just_error(TestError), |_: ()| Ok::<_, TestError>(1));
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+}
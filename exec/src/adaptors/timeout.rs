@@ -0,0 +1,177 @@
+use super::when_any::{when_any, WhenAny, WhenAnyReceiver};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{Sender, StopToken, TimedScheduler};
+use std::fmt;
+use std::time::Duration;
+
+/// Error of a [`timeout`] running out of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("timed out")
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Races `sender` against a timer of `duration` on `scheduler`, with
+/// [`when_any`]. When the timer wins, stop is requested on `sender` and the
+/// returned sender completes with [`TimedOut`] once it has stopped.
+pub fn timeout<S, Sch>(sender: S, scheduler: Sch, duration: Duration) -> Timeout<S, Sch> {
+    Timeout::new(sender, scheduler, duration)
+}
+
+pub struct Timeout<S, Sch> {
+    sender: S,
+    scheduler: Sch,
+    duration: Duration,
+}
+
+impl<S, Sch> Timeout<S, Sch> {
+    pub fn new(sender: S, scheduler: Sch, duration: Duration) -> Self {
+        Self {
+            sender,
+            scheduler,
+            duration,
+        }
+    }
+}
+
+/// Completes with [`TimedOut`] once `duration` has elapsed.
+pub struct TimedOutAfter<Sch> {
+    scheduler: Sch,
+    duration: Duration,
+}
+
+impl<Sch, R> Sender<R> for TimedOutAfter<Sch>
+where
+    Sch: TimedScheduler<TimedOutReceiver<R>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+    R::Error: From<TimedOut>,
+{
+    type Value = R::Value;
+    type Error = R::Error;
+
+    type Operation = <Sch::TimedSender as Sender<TimedOutReceiver<R>>>::Operation;
+
+    fn connect(mut self, receiver: R) -> Self::Operation {
+        self.scheduler
+            .schedule_after(self.duration)
+            .connect(TimedOutReceiver { receiver })
+    }
+}
+
+pub struct TimedOutReceiver<R> {
+    receiver: R,
+}
+
+impl<R> SetValue for TimedOutReceiver<R>
+where
+    R: SetError,
+    R::Error: From<TimedOut>,
+{
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.receiver.set_error(TimedOut.into());
+    }
+}
+
+impl<R: SetStopped> SetStopped for TimedOutReceiver<R> {
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<R: GetStopToken> GetStopToken for TimedOutReceiver<R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<S, Sch, R> Sender<R> for Timeout<S, Sch>
+where
+    S: Sender<WhenAnyReceiver<R>>,
+    Sch: TimedScheduler<TimedOutReceiver<WhenAnyReceiver<R>>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+    R::Error: From<TimedOut>,
+{
+    type Value = R::Value;
+    type Error = R::Error;
+
+    type Operation = <WhenAny<S, TimedOutAfter<Sch>> as Sender<R>>::Operation;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let timer = TimedOutAfter {
+            scheduler: self.scheduler,
+            duration: self.duration,
+        };
+        when_any(self.sender, timer).connect(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then;
+    use crate::consumers::sync_wait;
+    use crate::factories::just;
+    use exec_core::OperationState;
+    use exec_test::receivers::{ChannelReceiver, Completed};
+    use exec_test::schedulers::VirtualScheduler;
+    use exec_test::senders::NeverSender;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_timeout() {
+        let scheduler = VirtualScheduler::new();
+        let never = NeverSender::<i32>::new();
+        let stopped = never.stopped();
+        let (receiver, completed) = ChannelReceiver::<i32, TimedOut>::new();
+        let mut op = timeout(never, scheduler.clone(), Duration::from_secs(10)).connect(receiver);
+        op.start();
+
+        scheduler.advance(Duration::from_secs(9));
+        assert!(completed.try_recv().is_err());
+        scheduler.advance(Duration::from_secs(1));
+        assert_eq!(completed.try_recv(), Ok(Completed::Error(TimedOut)));
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_timeout_in_time() {
+        let mut scheduler = VirtualScheduler::new();
+        let sender = then(scheduler.schedule_after(Duration::from_secs(5)), |()| 1);
+        let (receiver, completed) = ChannelReceiver::<i32, TimedOut>::new();
+        let mut op = timeout(sender, scheduler.clone(), Duration::from_secs(10)).connect(receiver);
+        op.start();
+
+        scheduler.advance(Duration::from_secs(5));
+        assert_eq!(completed.try_recv(), Ok(Completed::Value(1)));
+        // The timer has been cancelled.
+        assert_eq!(scheduler.pending_timers(), 0);
+
+        let sender = timeout(just(2), scheduler.clone(), Duration::from_secs(10));
+        assert_eq!(sync_wait::<_, _, TimedOut>(sender), Ok(Some(2)));
+    }
+
+    #[test]
+    fn test_timeout_stopped() {
+        let scheduler = VirtualScheduler::new();
+        let (receiver, completed) = ChannelReceiver::<i32, TimedOut>::new();
+        let stop_source = receiver.stop_source();
+        let sender = timeout(
+            NeverSender::new(),
+            scheduler.clone(),
+            Duration::from_secs(10),
+        );
+        let mut op = sender.connect(receiver);
+        op.start();
+
+        stop_source.request_stop();
+        assert_eq!(completed.try_recv(), Ok(Completed::Stopped));
+        assert_eq!(scheduler.pending_timers(), 0);
+    }
+}
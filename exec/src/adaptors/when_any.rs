@@ -0,0 +1,216 @@
+use super::iteration::Completion;
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopCallback, StopSource, StopToken};
+use std::sync::{Arc, Mutex};
+
+/// Races `first` against `second`: the first of them to complete with a
+/// value or an error wins, and stop is requested on the other one.
+///
+/// The winning completion is forwarded once both have completed. If both
+/// complete with stopped, so does the returned sender.
+pub fn when_any<A, B>(first: A, second: B) -> WhenAny<A, B> {
+    WhenAny::new(first, second)
+}
+
+pub struct WhenAny<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> WhenAny<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+struct State<R>
+where
+    R: SetValue + SetError,
+{
+    inner: Mutex<Inner<R>>,
+    /// Stops the loser, or both senders when stop is requested downstream.
+    stop_source: StopSource,
+}
+
+struct Inner<R>
+where
+    R: SetValue + SetError,
+{
+    receiver: Option<R>,
+    winner: Option<Completion<R::Value, R::Error>>,
+    remaining: usize,
+}
+
+impl<R> State<R>
+where
+    R: SetValue + SetError + SetStopped,
+{
+    fn complete(&self, completion: Completion<R::Value, R::Error>) {
+        let won = {
+            let mut inner = self.inner.lock().unwrap();
+            let won = inner.winner.is_none() && !matches!(completion, Completion::Stopped);
+            if won {
+                inner.winner = Some(completion);
+            }
+            won
+        };
+        if won {
+            self.stop_source.request_stop();
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.remaining -= 1;
+        if inner.remaining > 0 {
+            return;
+        }
+        let receiver = inner.receiver.take().unwrap();
+        let winner = inner.winner.take();
+        drop(inner);
+        match winner {
+            Some(Completion::Value(value)) => receiver.set_value(value),
+            Some(Completion::Error(error)) => receiver.set_error(error),
+            Some(Completion::Stopped) | None => receiver.set_stopped(),
+        }
+    }
+}
+
+pub struct WhenAnyReceiver<R>
+where
+    R: SetValue + SetError,
+{
+    state: Arc<State<R>>,
+}
+
+impl<R> SetValue for WhenAnyReceiver<R>
+where
+    R: SetValue + SetError + SetStopped,
+{
+    type Value = R::Value;
+
+    fn set_value(self, value: Self::Value) {
+        self.state.complete(Completion::Value(value));
+    }
+}
+
+impl<R> SetError for WhenAnyReceiver<R>
+where
+    R: SetValue + SetError + SetStopped,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.state.complete(Completion::Error(error));
+    }
+}
+
+impl<R> SetStopped for WhenAnyReceiver<R>
+where
+    R: SetValue + SetError + SetStopped,
+{
+    fn set_stopped(self) {
+        self.state.complete(Completion::Stopped);
+    }
+}
+
+impl<R> GetStopToken for WhenAnyReceiver<R>
+where
+    R: SetValue + SetError,
+{
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+pub struct WhenAnyOperation<A, B, R>
+where
+    R: SetValue + SetError,
+{
+    first: A,
+    second: B,
+    state: Arc<State<R>>,
+    /// The stop token of the receiver.
+    stop_token: StopToken,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<A, B, R> OperationState for WhenAnyOperation<A, B, R>
+where
+    A: OperationState,
+    B: OperationState,
+    R: SetValue + SetError,
+{
+    fn start(&mut self) {
+        let stop_source = self.state.stop_source.clone();
+        self.stop_callback = Some(StopCallback::new(&self.stop_token, move || {
+            stop_source.request_stop();
+        }));
+        self.first.start();
+        self.second.start();
+    }
+}
+
+impl<A, B, R> Sender<R> for WhenAny<A, B>
+where
+    A: Sender<WhenAnyReceiver<R>>,
+    B: Sender<WhenAnyReceiver<R>>,
+    R: SetValue + SetError + SetStopped + GetStopToken,
+{
+    type Value = R::Value;
+    type Error = R::Error;
+
+    type Operation = WhenAnyOperation<A::Operation, B::Operation, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let stop_token = receiver.get_stop_token();
+        let state = Arc::new(State {
+            inner: Mutex::new(Inner {
+                receiver: Some(receiver),
+                winner: None,
+                remaining: 2,
+            }),
+            stop_source: StopSource::new(),
+        });
+        WhenAnyOperation {
+            first: self.first.connect(WhenAnyReceiver {
+                state: state.clone(),
+            }),
+            second: self.second.connect(WhenAnyReceiver {
+                state: state.clone(),
+            }),
+            state,
+            stop_token,
+            stop_callback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumers::{sync_wait, sync_wait_for, SyncWaitError};
+    use crate::factories::{just, just_error};
+    use exec_test::errors::TestError;
+    use exec_test::senders::NeverSender;
+    use std::convert::Infallible;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[test]
+    fn test_when_any() {
+        let never = NeverSender::new();
+        let stopped = never.stopped();
+        let sender = when_any(never, just(1));
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(1)));
+        assert!(stopped.load(Ordering::SeqCst));
+
+        let sender = when_any(just_error(TestError), NeverSender::<i32>::new());
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+
+    #[test]
+    fn test_when_any_stopped() {
+        let sender = when_any(NeverSender::<()>::new(), NeverSender::new());
+        let result = sync_wait_for::<_, _, Infallible>(sender, Duration::from_millis(10));
+        assert_eq!(result, Err(SyncWaitError::TimedOut));
+    }
+}
@@ -0,0 +1,419 @@
+use crate::consumers::start_detached::{ErrorHandler, ReportUnhandled};
+use crate::consumers::submit;
+use crate::consumers::submit::SubmitReceiver;
+use crate::consumers::AwaitResult;
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopSource, StopToken};
+use std::error::Error;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// Keeps track of work spawned into it.
+///
+/// Unlike [`start_detached`](crate::start_detached), the spawned work can be
+/// waited for with [`on_empty`](AsyncScope::on_empty) and stopped with
+/// [`request_stop`](AsyncScope::request_stop), e.g. before shutting down the
+/// execution context it runs on.
+pub struct AsyncScope {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: Mutex<ScopeState>,
+    stop_source: StopSource,
+}
+
+struct ScopeState {
+    count: usize,
+    waiters: Vec<Box<dyn FnOnce() + Send>>,
+}
+
+impl Inner {
+    fn add(&self) {
+        self.state.lock().unwrap().count += 1;
+    }
+
+    fn remove(&self) {
+        let waiters = {
+            let mut state = self.state.lock().unwrap();
+            state.count -= 1;
+            if state.count > 0 {
+                return;
+            }
+            std::mem::take(&mut state.waiters)
+        };
+
+        for waiter in waiters {
+            waiter();
+        }
+    }
+}
+
+impl AsyncScope {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(ScopeState {
+                    count: 0,
+                    waiters: Vec::new(),
+                }),
+                stop_source: StopSource::new(),
+            }),
+        }
+    }
+
+    /// Starts `sender` in the scope, its value is discarded and an error is
+    /// reported like for [`start_detached`](crate::start_detached).
+    ///
+    /// Once stop has been requested on the scope, `sender` is dropped without
+    /// being started.
+    pub fn spawn<S, V, E>(&self, sender: S)
+    where
+        S: Sender<SubmitReceiver<SpawnReceiver<V, E>>, Value = V, Error = E>,
+    {
+        if self.inner.stop_source.stop_requested() {
+            return;
+        }
+
+        self.inner.add();
+        submit(
+            sender,
+            SpawnReceiver {
+                scope: self.inner.clone(),
+                _phantom: PhantomData,
+            },
+        );
+    }
+
+    /// Starts `sender` in the scope and returns a sender of its result.
+    ///
+    /// The work runs eagerly, the result is kept until the returned sender is
+    /// started. Once stop has been requested on the scope, `sender` isn't
+    /// started and the returned sender completes with stopped.
+    pub fn spawn_future<S, V, E>(&self, sender: S) -> SpawnFuture<V, E>
+    where
+        S: Sender<SubmitReceiver<SpawnFutureReceiver<V, E>>, Value = V, Error = E>,
+    {
+        if self.inner.stop_source.stop_requested() {
+            let state = FutureState::Complete(AwaitResult::Stopped);
+            return SpawnFuture {
+                shared: Arc::new(Mutex::new(state)),
+            };
+        }
+
+        let shared = Arc::new(Mutex::new(FutureState::Pending));
+
+        self.inner.add();
+        submit(
+            sender,
+            SpawnFutureReceiver {
+                scope: self.inner.clone(),
+                shared: shared.clone(),
+            },
+        );
+
+        SpawnFuture { shared }
+    }
+
+    /// Requests stop on all work spawned in the scope.
+    pub fn request_stop(&self) -> bool {
+        self.inner.stop_source.request_stop()
+    }
+
+    pub fn get_stop_token(&self) -> StopToken {
+        self.inner.stop_source.token()
+    }
+
+    /// Sender completing once all work spawned in the scope has completed.
+    pub fn on_empty(&self) -> OnEmpty {
+        OnEmpty {
+            scope: self.inner.clone(),
+        }
+    }
+}
+
+impl Default for AsyncScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct SpawnReceiver<V, E> {
+    scope: Arc<Inner>,
+    _phantom: PhantomData<fn(V, E)>,
+}
+
+impl<V, E> SetValue for SpawnReceiver<V, E> {
+    type Value = V;
+
+    fn set_value(self, _value: Self::Value) {
+        self.scope.remove();
+    }
+}
+
+impl<V, E> SetError for SpawnReceiver<V, E>
+where
+    E: Error,
+    ReportUnhandled: ErrorHandler<E>,
+{
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        ReportUnhandled.handle_error(error);
+        self.scope.remove();
+    }
+}
+
+impl<V, E> SetStopped for SpawnReceiver<V, E> {
+    fn set_stopped(self) {
+        self.scope.remove();
+    }
+}
+
+impl<V, E> GetStopToken for SpawnReceiver<V, E> {
+    fn get_stop_token(&self) -> StopToken {
+        self.scope.stop_source.token()
+    }
+}
+
+enum FutureState<V, E> {
+    Pending,
+    Complete(AwaitResult<V, E>),
+    Waiting(Box<dyn FnOnce(AwaitResult<V, E>) + Send>),
+    Done,
+}
+
+fn complete<V, E>(shared: &Mutex<FutureState<V, E>>, result: AwaitResult<V, E>) {
+    let mut state = shared.lock().unwrap();
+    match std::mem::replace(&mut *state, FutureState::Done) {
+        FutureState::Waiting(continuation) => {
+            drop(state);
+            continuation(result);
+        }
+        FutureState::Pending => *state = FutureState::Complete(result),
+        FutureState::Complete(_) | FutureState::Done => unreachable!(),
+    }
+}
+
+pub struct SpawnFutureReceiver<V, E> {
+    scope: Arc<Inner>,
+    shared: Arc<Mutex<FutureState<V, E>>>,
+}
+
+impl<V, E> SpawnFutureReceiver<V, E> {
+    fn complete(self, result: AwaitResult<V, E>) {
+        complete(&self.shared, result);
+        self.scope.remove();
+    }
+}
+
+impl<V, E> SetValue for SpawnFutureReceiver<V, E> {
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        self.complete(AwaitResult::Value(value));
+    }
+}
+
+impl<V, E: Error> SetError for SpawnFutureReceiver<V, E> {
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        self.complete(AwaitResult::Error(error));
+    }
+}
+
+impl<V, E> SetStopped for SpawnFutureReceiver<V, E> {
+    fn set_stopped(self) {
+        self.complete(AwaitResult::Stopped);
+    }
+}
+
+impl<V, E> GetStopToken for SpawnFutureReceiver<V, E> {
+    fn get_stop_token(&self) -> StopToken {
+        self.scope.stop_source.token()
+    }
+}
+
+/// Sender of the result of work spawned with
+/// [`spawn_future`](AsyncScope::spawn_future).
+pub struct SpawnFuture<V, E> {
+    shared: Arc<Mutex<FutureState<V, E>>>,
+}
+
+pub struct SpawnFutureOperation<V, E, R> {
+    shared: Arc<Mutex<FutureState<V, E>>>,
+    receiver: Option<R>,
+}
+
+impl<V, E, R> OperationState for SpawnFutureOperation<V, E, R>
+where
+    R: SetValue<Value = V> + SetError<Error = E> + SetStopped + Send + 'static,
+{
+    fn start(&mut self) {
+        let Some(receiver) = self.receiver.take() else {
+            return;
+        };
+        let deliver = move |result: AwaitResult<V, E>| result.complete(receiver);
+
+        let mut state = self.shared.lock().unwrap();
+        match std::mem::replace(&mut *state, FutureState::Done) {
+            FutureState::Complete(result) => {
+                drop(state);
+                deliver(result);
+            }
+            FutureState::Pending => *state = FutureState::Waiting(Box::new(deliver)),
+            FutureState::Waiting(_) | FutureState::Done => unreachable!(),
+        }
+    }
+}
+
+impl<V, E, R> Sender<R> for SpawnFuture<V, E>
+where
+    R: SetValue<Value = V> + SetError<Error = E> + SetStopped + Send + 'static,
+{
+    type Value = V;
+    type Error = E;
+
+    type Operation = SpawnFutureOperation<V, E, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        SpawnFutureOperation {
+            shared: self.shared,
+            receiver: Some(receiver),
+        }
+    }
+}
+
+/// Sender returned by [`AsyncScope::on_empty`].
+pub struct OnEmpty {
+    scope: Arc<Inner>,
+}
+
+pub struct OnEmptyOperation<R> {
+    scope: Arc<Inner>,
+    receiver: Option<R>,
+}
+
+impl<R> OperationState for OnEmptyOperation<R>
+where
+    R: SetValue<Value = ()> + Send + 'static,
+{
+    fn start(&mut self) {
+        let Some(receiver) = self.receiver.take() else {
+            return;
+        };
+
+        let mut state = self.scope.state.lock().unwrap();
+        if state.count > 0 {
+            state.waiters.push(Box::new(move || receiver.set_value(())));
+        } else {
+            drop(state);
+            receiver.set_value(());
+        }
+    }
+}
+
+impl<R> Sender<R> for OnEmpty
+where
+    R: SetValue<Value = ()> + Send + 'static,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = OnEmptyOperation<R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        OnEmptyOperation {
+            scope: self.scope,
+            receiver: Some(receiver),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumers::sync_wait;
+    use crate::factories::{just, just_error};
+    use crate::{from_future_on, then};
+    use exec_core::Scheduler;
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use exec_test::senders::NeverSender;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_on_empty() {
+        let scope = AsyncScope::new();
+        assert_eq!(sync_wait(scope.on_empty()), Ok(Some(())));
+    }
+
+    #[test]
+    fn test_spawn() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+        let scope = AsyncScope::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..10 {
+            let count = count.clone();
+            scope.spawn(then(scheduler.schedule(), move |_| {
+                count.fetch_add(1, Ordering::SeqCst);
+            }));
+        }
+
+        sync_wait(scope.on_empty()).unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    fn test_spawn_future() {
+        let context = SingleThreadContext::new();
+        let scheduler = context.get_scheduler();
+        let scope = AsyncScope::new();
+
+        let future =
+            scope.spawn_future(from_future_on(scheduler, async { Ok::<_, TestError>(42) }));
+        assert_eq!(sync_wait(future), Ok(Some(42)));
+
+        let future = scope.spawn_future(just_error(TestError));
+        assert_eq!(sync_wait::<_, (), _>(future), Err(TestError));
+        sync_wait(scope.on_empty()).unwrap();
+    }
+
+    #[test]
+    fn test_request_stop() {
+        let context = SingleThreadContext::new();
+        let scope = AsyncScope::new();
+        let sender = NeverSender::<()>::new();
+        let stopped = sender.stopped();
+        scope.spawn(sender);
+
+        let future = scope.spawn_future(from_future_on(
+            context.get_scheduler(),
+            futures::future::pending::<Result<i32, TestError>>(),
+        ));
+        assert!(scope.request_stop());
+        assert!(stopped.load(Ordering::SeqCst));
+        assert_eq!(sync_wait(future), Ok(None));
+        sync_wait(scope.on_empty()).unwrap();
+    }
+
+    #[test]
+    fn test_spawn_after_request_stop() {
+        let scope = AsyncScope::new();
+        scope.request_stop();
+
+        let started = Arc::new(AtomicUsize::new(0));
+        let count = started.clone();
+        scope.spawn(then(just(()), move |()| {
+            count.fetch_add(1, Ordering::SeqCst);
+        }));
+        // Would complete with the error if it was started.
+        let future = scope.spawn_future(just_error(TestError));
+        assert_eq!(sync_wait::<_, (), _>(future), Ok(None));
+        assert_eq!(started.load(Ordering::SeqCst), 0);
+        assert_eq!(sync_wait(scope.on_empty()), Ok(Some(())));
+    }
+}
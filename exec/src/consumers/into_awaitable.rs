@@ -1,133 +1,312 @@
-use exec_core::receiver::{SetError, SetValue};
-use exec_core::{OperationState, Sender};
-use std::cell::UnsafeCell;
+use super::WaitResult;
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopCallback, StopSource, StopToken};
+use std::cell::{RefCell, UnsafeCell};
 use std::error::Error;
 use std::future::Future;
 use std::pin::Pin;
-use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll, Waker};
 
-#[allow(dead_code)]
-#[derive(Debug)]
-pub enum AwaitResult<V, E> {
-    Value(V),
-    Error(E),
-    Stopped,
+thread_local! {
+    /// Stop token of the task being polled on this thread.
+    static CURRENT_STOP_TOKEN: RefCell<StopToken> = RefCell::new(StopToken::never());
 }
 
+/// Runs `f` with `token` as the stop token of the task being polled.
+///
+/// Awaitables started from within `f` request stop on their sender when
+/// stop is requested on `token`.
+pub(crate) fn with_stop_token<T>(token: &StopToken, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT_STOP_TOKEN.replace(token.clone());
+    let _restore = scopeguard::guard(previous, |previous| CURRENT_STOP_TOKEN.set(previous));
+    f()
+}
+
+/// How the sender of an awaitable completed.
+pub type AwaitResult<V, E> = WaitResult<V, E>;
+
 struct SharedState<V, E> {
-    result: Option<AwaitResult<V, E>>,
-    waker: Option<Waker>,
+    /// Set once `result` has been written, the receiver never touches
+    /// `result` afterwards.
+    complete: AtomicBool,
+    result: UnsafeCell<Option<AwaitResult<V, E>>>,
+    waker: Mutex<Option<Waker>>,
+    stop_source: StopSource,
+    /// Operation of an awaitable dropped before completion, it is kept alive
+    /// until the receiver is completed.
+    orphan: Mutex<Option<Orphan>>,
 }
 
-pub struct Receiver<V, E> {
-    state: Rc<UnsafeCell<SharedState<V, E>>>,
+unsafe impl<V: Send, E: Send> Send for SharedState<V, E> {}
+unsafe impl<V: Send, E: Send> Sync for SharedState<V, E> {}
+
+/// A type-erased boxed operation.
+pub(crate) struct Orphan {
+    operation: *mut (),
+    drop_fn: unsafe fn(*mut ()),
 }
 
-impl<V, E> SetValue for Receiver<V, E> {
-    type Value = V;
+impl Orphan {
+    pub(crate) fn new<O>(operation: Box<O>) -> Self {
+        Self {
+            operation: Box::into_raw(operation) as *mut (),
+            drop_fn: |operation| unsafe { drop(Box::from_raw(operation as *mut O)) },
+        }
+    }
+}
 
-    fn set_value(self, value: Self::Value) {
+impl Drop for Orphan {
+    fn drop(&mut self) {
+        unsafe { (self.drop_fn)(self.operation) }
+    }
+}
+
+pub struct AwaitableReceiver<V, E> {
+    state: Arc<SharedState<V, E>>,
+}
+
+impl<V, E> AwaitableReceiver<V, E> {
+    fn complete(self, result: AwaitResult<V, E>) {
         unsafe {
-            let _ = (*self.state.get()).result.insert(AwaitResult::Value(value));
-            if let Some(waker) = (*self.state.get()).waker.take() {
-                waker.wake();
-            }
+            let _ = (*self.state.result.get()).insert(result);
         }
+
+        let orphan = {
+            let mut orphan = self.state.orphan.lock().unwrap();
+            self.state.complete.store(true, Ordering::Release);
+            orphan.take()
+        };
+
+        if let Some(waker) = self.state.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+
+        // Nobody awaits the result anymore, release the operation. The
+        // receiver has been moved out of it, so this is the last access.
+        drop(orphan);
     }
 }
 
-impl<V, E: Error> SetError for Receiver<V, E> {
+impl<V, E> SetValue for AwaitableReceiver<V, E> {
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        self.complete(AwaitResult::Value(value));
+    }
+}
+
+impl<V, E: Error> SetError for AwaitableReceiver<V, E> {
     type Error = E;
 
     fn set_error(self, error: Self::Error) {
-        unsafe {
-            let _ = (*self.state.get()).result.insert(AwaitResult::Error(error));
-            if let Some(waker) = (*self.state.get()).waker.take() {
-                waker.wake();
-            }
-        }
+        self.complete(AwaitResult::Error(error));
+    }
+}
+
+impl<V, E> SetStopped for AwaitableReceiver<V, E> {
+    fn set_stopped(self) {
+        self.complete(AwaitResult::Stopped);
     }
 }
 
+impl<V, E> GetStopToken for AwaitableReceiver<V, E> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+/// A future driving a sender to completion.
+///
+/// The sender is connected and started on the first poll, the operation is
+/// boxed so it never moves while running. Dropping the future before the
+/// sender completes requests stop, the operation is released once it has
+/// completed. When awaited from a [`Task`](crate::Task), stop requested on
+/// the task is forwarded to the sender.
 pub struct SenderAwaitable<S, V, E>
 where
-    S: Sender<Receiver<V, E>, Value = V, Error = E>,
+    S: Sender<AwaitableReceiver<V, E>, Value = V, Error = E>,
 {
-    state: Rc<UnsafeCell<SharedState<V, E>>>,
-    operation: S::Operation,
+    state: Arc<SharedState<V, E>>,
+    sender: Option<S>,
+    operation: Option<Box<S::Operation>>,
+    stop_callback: Option<StopCallback>,
 }
 
 impl<S, V, E> SenderAwaitable<S, V, E>
 where
-    S: Sender<Receiver<V, E>, Value = V, Error = E>,
+    S: Sender<AwaitableReceiver<V, E>, Value = V, Error = E>,
 {
-    pub(crate) fn new(sender: S) -> Self {
-        let state = Rc::new(UnsafeCell::new(SharedState {
-            result: None,
-            waker: None,
-        }));
-
-        let receiver = Receiver {
-            state: state.clone(),
-        };
+    pub fn new(sender: S) -> Self {
+        let state = Arc::new(SharedState {
+            complete: AtomicBool::new(false),
+            result: UnsafeCell::new(None),
+            waker: Mutex::new(None),
+            stop_source: StopSource::new(),
+            orphan: Mutex::new(None),
+        });
 
         Self {
             state,
-            operation: sender.connect(receiver),
+            sender: Some(sender),
+            operation: None,
+            stop_callback: None,
         }
     }
+
+    fn take_result(&self) -> Option<AwaitResult<V, E>> {
+        if !self.state.complete.load(Ordering::Acquire) {
+            return None;
+        }
+        let result = unsafe { (*self.state.result.get()).take() };
+        Some(result.expect("SenderAwaitable polled after completion"))
+    }
 }
 
 impl<S, V, E> Future for SenderAwaitable<S, V, E>
 where
-    S: Sender<Receiver<V, E>, Value = V, Error = E>,
+    S: Sender<AwaitableReceiver<V, E>, Value = V, Error = E>,
 {
     type Output = Result<Option<V>, E>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Nothing is structurally pinned, the operation lives in its own box.
         let me = unsafe { self.get_unchecked_mut() };
 
-        if let Some(result) = unsafe { (*me.state.get()).result.take() } {
-            match result {
-                AwaitResult::Value(value) => Poll::Ready(Ok(Some(value))),
-                AwaitResult::Error(error) => Poll::Ready(Err(error)),
-                AwaitResult::Stopped => Poll::Ready(Ok(None)),
-            }
-        } else {
-            unsafe {
-                let _ = (*me.state.get()).waker.insert(cx.waker().clone());
+        let result = me.take_result().or_else(|| {
+            // Register before starting, the sender may complete on another
+            // thread right away.
+            *me.state.waker.lock().unwrap() = Some(cx.waker().clone());
+
+            if let Some(sender) = me.sender.take() {
+                let token = CURRENT_STOP_TOKEN.with_borrow(StopToken::clone);
+                if token.stop_possible() {
+                    let stop_source = me.state.stop_source.clone();
+                    me.stop_callback = Some(StopCallback::new(&token, move || {
+                        stop_source.request_stop();
+                    }));
+                }
+
+                let receiver = AwaitableReceiver {
+                    state: me.state.clone(),
+                };
+                me.operation
+                    .insert(Box::new(sender.connect(receiver)))
+                    .start();
             }
-            me.operation.start();
-            Poll::Pending
+
+            me.take_result()
+        });
+
+        match result {
+            Some(AwaitResult::Value(value)) => Poll::Ready(Ok(Some(value))),
+            Some(AwaitResult::Error(error)) => Poll::Ready(Err(error)),
+            Some(AwaitResult::Stopped) => Poll::Ready(Ok(None)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<S, V, E> Drop for SenderAwaitable<S, V, E>
+where
+    S: Sender<AwaitableReceiver<V, E>, Value = V, Error = E>,
+{
+    fn drop(&mut self) {
+        let Some(operation) = self.operation.take() else {
+            return;
+        };
+
+        let mut orphan = self.state.orphan.lock().unwrap();
+        if self.state.complete.load(Ordering::Acquire) {
+            drop(orphan);
+            drop(operation);
+            return;
         }
+        *orphan = Some(Orphan::new(operation));
+        drop(orphan);
+
+        self.state.stop_source.request_stop();
+    }
+}
+
+/// Awaits any sender from async Rust.
+///
+/// The output of the future is derived from the completions of the sender:
+/// `Ok(Some(value))`, `Err(error)` or `Ok(None)` when stopped.
+pub trait IntoAwaitable<V, E>:
+    Sender<AwaitableReceiver<V, E>, Value = V, Error = E> + Sized
+{
+    fn into_awaitable(self) -> SenderAwaitable<Self, V, E> {
+        SenderAwaitable::new(self)
     }
 }
 
+impl<S, V, E> IntoAwaitable<V, E> for S where
+    S: Sender<AwaitableReceiver<V, E>, Value = V, Error = E>
+{
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::consumers::SenderAwaitable;
+    use crate::consumers::{IntoAwaitable, SenderAwaitable};
     use crate::factories::just_error;
     use crate::{just, then};
+    use exec_core::Scheduler;
+    use exec_executor::SingleThreadContext;
     use exec_test::errors::TestError;
+    use exec_test::senders::NeverSender;
     use futures::executor::block_on;
+    use futures::task::noop_waker;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::atomic::Ordering;
+    use std::task::{Context, Poll};
+    use std::thread;
 
     #[test]
     fn test_awaitable() {
         block_on(async {
             let sender = just(1);
             let sender = then(sender, |x| x + 1);
-            let awaitable = SenderAwaitable::new(sender);
-            println!("{:?}", awaitable.await);
+            let sender = then(sender, |x| x * 2);
+            assert_eq!(sender.into_awaitable().await, Ok(Some(4)));
         });
     }
 
     #[test]
     fn test_awaitable_error() {
         block_on(async {
-            let sender = just_error(TestError);
-            let awaitable = SenderAwaitable::new(sender);
-            println!("{:?}", awaitable.await);
+            let sender = then(just_error(TestError), |_: ()| 1);
+            assert_eq!(sender.into_awaitable().await, Err(TestError));
         });
     }
+
+    #[test]
+    fn test_awaitable_on_other_thread() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+        let awaitable =
+            then(scheduler.schedule(), move |_| thread::current().id()).into_awaitable();
+
+        // The future is `Send` and completes on the context thread.
+        let worker = thread::spawn(move || block_on(awaitable)).join().unwrap();
+        assert_ne!(worker.unwrap(), Some(thread::current().id()));
+    }
+
+    #[test]
+    fn test_awaitable_drop_requests_stop() {
+        let sender = NeverSender::<i32>::new();
+        let stopped = sender.stopped();
+
+        {
+            let mut awaitable = pin!(SenderAwaitable::new(sender));
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+            assert!(matches!(awaitable.as_mut().poll(&mut cx), Poll::Pending));
+            assert!(matches!(awaitable.as_mut().poll(&mut cx), Poll::Pending));
+        }
+
+        assert!(stopped.load(Ordering::SeqCst));
+    }
 }
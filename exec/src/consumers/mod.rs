@@ -1,10 +1,15 @@
 mod sync_wait;
-pub use sync_wait::sync_wait;
+pub use sync_wait::{sync_wait, sync_wait_for, sync_wait_until, SyncWaitError, WaitResult};
 
 mod into_awaitable;
 pub mod start_detached;
 pub mod submit;
+#[cfg(feature = "tokio")]
+pub mod tokio_spawn;
 
-pub use into_awaitable::SenderAwaitable;
-pub use start_detached::start_detached;
+pub(crate) use into_awaitable::{with_stop_token, Orphan};
+pub use into_awaitable::{AwaitResult, AwaitableReceiver, IntoAwaitable, SenderAwaitable};
+pub use start_detached::{start_detached, start_detached_with};
 pub use submit::submit;
+#[cfg(feature = "tokio")]
+pub use tokio_spawn::{spawn_tokio, spawn_tokio_on};
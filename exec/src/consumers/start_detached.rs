@@ -1,47 +1,147 @@
 use crate::consumers::submit;
-use crate::consumers::submit::SubmitReceiver;
-use exec_core::receiver::{SetError, SetValue};
-use exec_core::Sender;
+use crate::consumers::submit::{submit_with_allocator, SubmitReceiver};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{Allocator, Sender, StopToken};
+use exec_executor::RunLoop;
 use std::error::Error;
+use std::sync::RwLock;
 
+type ErrorSink = dyn Fn(&dyn Error) + Send + Sync;
+
+static ERROR_SINK: RwLock<Option<Box<ErrorSink>>> = RwLock::new(None);
+
+/// Starts `sender` and forgets about it.
+///
+/// An error is passed to the unhandled-error hook of the run loop it is
+/// reported on, see [`RunLoop::set_unhandled_error_hook`], or else to the
+/// error sink. A stopped completion is ignored.
 pub fn start_detached<S, V, E>(sender: S)
 where
     S: Sender<SubmitReceiver<StartDetachedReceiver<V, E>>, Value = V, Error = E>,
 {
-    submit(
+    submit(sender, StartDetachedReceiver::new(ReportUnhandled))
+}
+
+/// Like [`start_detached`], but an error is passed to `handler`.
+pub fn start_detached_with<S, V, E, F>(sender: S, handler: F)
+where
+    S: Sender<SubmitReceiver<StartDetachedReceiver<V, E, F>>, Value = V, Error = E>,
+    F: FnOnce(E),
+{
+    submit(sender, StartDetachedReceiver::new(handler))
+}
+
+/// Like [`start_detached`], with the operation allocated by `allocator`.
+pub fn start_detached_with_allocator<S, V, E, A>(sender: S, allocator: A)
+where
+    S: Sender<SubmitReceiver<StartDetachedReceiver<V, E>>, Value = V, Error = E>,
+    A: Allocator,
+{
+    submit_with_allocator(
         sender,
-        StartDetachedReceiver {
-            _phantom: std::marker::PhantomData,
-        },
+        StartDetachedReceiver::new(ReportUnhandled),
+        allocator,
     )
 }
 
-pub struct StartDetachedReceiver<V, E> {
+/// Replaces the sink of the errors nothing else handles. The default sink
+/// prints them to stderr.
+pub fn set_error_sink<F>(sink: F)
+where
+    F: Fn(&dyn Error) + Send + Sync + 'static,
+{
+    *ERROR_SINK.write().unwrap() = Some(Box::new(sink));
+}
+
+/// Reports an error nobody handles to the current run loop's unhandled-error
+/// hook, or else to the error sink.
+pub fn report_unhandled_error(error: &dyn Error) {
+    if let Some(run_loop) = RunLoop::current() {
+        if run_loop.report_unhandled_error(error) {
+            return;
+        }
+    }
+
+    match ERROR_SINK.read().unwrap().as_ref() {
+        Some(sink) => sink(error),
+        None => eprintln!("unhandled error in detached operation: {}", error),
+    }
+}
+
+/// Handles the error of a detached operation.
+pub trait ErrorHandler<E> {
+    fn handle_error(self, error: E);
+}
+
+impl<E, F> ErrorHandler<E> for F
+where
+    F: FnOnce(E),
+{
+    fn handle_error(self, error: E) {
+        self(error)
+    }
+}
+
+/// Error handler of [`start_detached`], see [`report_unhandled_error`].
+pub struct ReportUnhandled;
+
+impl<E: Error> ErrorHandler<E> for ReportUnhandled {
+    fn handle_error(self, error: E) {
+        report_unhandled_error(&error);
+    }
+}
+
+pub struct StartDetachedReceiver<V, E, H = ReportUnhandled> {
+    handler: H,
     _phantom: std::marker::PhantomData<(V, E)>,
 }
 
-impl<V, E> SetValue for StartDetachedReceiver<V, E> {
+impl<V, E, H> StartDetachedReceiver<V, E, H> {
+    fn new(handler: H) -> Self {
+        Self {
+            handler,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<V, E, H> SetValue for StartDetachedReceiver<V, E, H> {
     type Value = V;
 
     fn set_value(self, _value: Self::Value) {}
 }
 
-impl<V, E: Error> SetError for StartDetachedReceiver<V, E> {
+impl<V, E: Error, H: ErrorHandler<E>> SetError for StartDetachedReceiver<V, E, H> {
     type Error = E;
 
     fn set_error(self, error: Self::Error) {
-        panic!(
-            "StartDetachedReceiver::set_error called with error: {:?}",
-            error
-        );
+        self.handler.handle_error(error);
+    }
+}
+
+impl<V, E, H> SetStopped for StartDetachedReceiver<V, E, H> {
+    fn set_stopped(self) {}
+}
+
+impl<V, E, H> GetStopToken for StartDetachedReceiver<V, E, H> {
+    fn get_stop_token(&self) -> StopToken {
+        StopToken::never()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::adaptors::then_try;
     use crate::factories::{just, just_error};
+    use crate::from_future_on;
+    use exec_core::allocator::SlabAllocator;
+    use exec_core::Scheduler;
+    use exec_executor::SingleThreadContext;
     use exec_test::errors::TestError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::thread;
 
     #[test]
     fn test_start_detached() {
@@ -50,9 +150,65 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    fn test_start_detached_with_allocator() {
+        let allocator = SlabAllocator::new();
+        start_detached_with_allocator(just(1), allocator.clone());
+        assert_eq!((allocator.live(), allocator.pooled()), (0, 1));
+    }
+
+    #[test]
     fn test_start_detached_with_error() {
-        let sender = just_error(TestError);
-        start_detached(sender);
+        // Reported to the hook of the run loop the error happens on, the
+        // process-wide sink is left alone.
+        let run_loop = RunLoop::new();
+        let mut scheduler = run_loop.get_scheduler();
+        let errors = Arc::new(AtomicUsize::new(0));
+        run_loop.set_unhandled_error_hook({
+            let errors = errors.clone();
+            move |_| {
+                errors.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        start_detached(then_try(scheduler.schedule(), |_| Err::<(), _>(TestError)));
+        run_loop.run_until(|| errors.load(Ordering::SeqCst) > 0);
+        assert_eq!(errors.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_start_detached_with_handler() {
+        let errors = Arc::new(AtomicUsize::new(0));
+        let handler = {
+            let errors = errors.clone();
+            move |_: TestError| {
+                errors.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        start_detached_with(just_error(TestError), handler);
+        assert_eq!(errors.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_start_detached_stopped() {
+        let run_loop = RunLoop::new();
+        let mut scheduler = run_loop.get_scheduler();
+        drop(run_loop);
+        start_detached(scheduler.schedule());
+    }
+
+    #[test]
+    fn test_start_detached_context_hook() {
+        let context = SingleThreadContext::new();
+        let scheduler = context.get_scheduler();
+        let (tx, rx) = mpsc::channel();
+        context.set_unhandled_error_hook(move |error| {
+            let message = (error.to_string(), thread::current().id());
+            tx.send(message).unwrap();
+        });
+
+        start_detached(from_future_on(scheduler, async { Err::<(), _>(TestError) }));
+        let (message, id) = rx.recv().unwrap();
+        assert_eq!(message, TestError.to_string());
+        assert_ne!(id, thread::current().id());
     }
 }
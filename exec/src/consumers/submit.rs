@@ -1,28 +1,71 @@
-use exec_core::receiver::{SetError, SetValue};
-use exec_core::{OperationState, Sender};
+use exec_core::allocator::SmallBlocks;
+use exec_core::receiver::{
+    GetAllocator, GetScheduler, GetStopToken, SetError, SetStopped, SetValue,
+};
+use exec_core::{Allocator, OperationState, Sender, StopToken};
 use scopeguard::defer;
+use std::alloc::Layout;
 use std::cell::UnsafeCell;
-use std::ptr::NonNull;
+use std::mem::ManuallyDrop;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+/// Starts `sender` with `receiver`, the operation lives on the heap until it
+/// completes.
+///
+/// Small operations are stored in blocks recycled through a cache of the
+/// current thread, see [`SmallBlocks`], so that submitting them over and over
+/// doesn't allocate.
 pub fn submit<S, R>(sender: S, receiver: R)
 where
     S: Sender<SubmitReceiver<R>>,
 {
-    let op = Box::leak(SubmitOperation::new(sender, receiver));
-    op.op_state.as_mut().unwrap().start();
+    submit_with_allocator(sender, receiver, SmallBlocks)
 }
 
+/// Like [`submit`], with the operation allocated by the allocator of the
+/// receiver.
+pub fn submit_with_env_allocator<S, R>(sender: S, receiver: R)
+where
+    S: Sender<SubmitReceiver<R>>,
+    R: GetAllocator,
+{
+    let allocator = receiver.get_allocator();
+    submit_with_allocator(sender, receiver, allocator)
+}
+
+/// Like [`submit`], with the operation allocated by `allocator`.
+pub fn submit_with_allocator<S, R, A>(sender: S, receiver: R, allocator: A)
+where
+    S: Sender<SubmitReceiver<R>>,
+    A: Allocator,
+{
+    let op = SubmitOperation::new(sender, receiver, allocator);
+    unsafe { (*op.as_ptr()).op_state.as_mut().unwrap().start() };
+}
+
+/// Number of operations started by [`submit`] and its variants that have not
+/// completed yet.
+pub fn live_operations() -> usize {
+    LIVE_OPERATIONS.load(Ordering::Relaxed)
+}
+
+static LIVE_OPERATIONS: AtomicUsize = AtomicUsize::new(0);
+
 pub struct SubmitReceiver<R> {
     op_state: NonNull<SubmitOperationBase<R>>,
 }
 
+// The operation is leaked on the heap until the receiver completes.
+unsafe impl<R: Send> Send for SubmitReceiver<R> {}
+
 impl<R: SetValue> SetValue for SubmitReceiver<R> {
     type Value = R::Value;
 
     fn set_value(self, value: Self::Value) {
         unsafe {
             defer! {
-                (self.op_state.as_ref().delete_fn)(self.op_state.as_ptr());
+                SubmitOperationBase::delete(self.op_state);
             }
             (*self.op_state.as_ref().receiver.get())
                 .take()
@@ -38,7 +81,7 @@ impl<R: SetError> SetError for SubmitReceiver<R> {
     fn set_error(self, error: Self::Error) {
         unsafe {
             defer! {
-                (self.op_state.as_ref().delete_fn)(self.op_state.as_ptr());
+                SubmitOperationBase::delete(self.op_state);
             }
             (*self.op_state.as_ref().receiver.get())
                 .take()
@@ -48,46 +91,117 @@ impl<R: SetError> SetError for SubmitReceiver<R> {
     }
 }
 
+impl<R: SetStopped> SetStopped for SubmitReceiver<R> {
+    fn set_stopped(self) {
+        unsafe {
+            defer! {
+                SubmitOperationBase::delete(self.op_state);
+            }
+            (*self.op_state.as_ref().receiver.get())
+                .take()
+                .unwrap()
+                .set_stopped();
+        }
+    }
+}
+
+impl<R: GetStopToken> GetStopToken for SubmitReceiver<R> {
+    fn get_stop_token(&self) -> StopToken {
+        unsafe {
+            (*self.op_state.as_ref().receiver.get())
+                .as_ref()
+                .unwrap()
+                .get_stop_token()
+        }
+    }
+}
+
+impl<R: GetScheduler> GetScheduler for SubmitReceiver<R> {
+    type Scheduler = R::Scheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        unsafe {
+            (*self.op_state.as_ref().receiver.get())
+                .as_ref()
+                .unwrap()
+                .get_scheduler()
+        }
+    }
+}
+
 struct SubmitOperationBase<R> {
     receiver: UnsafeCell<Option<R>>,
-    delete_fn: unsafe fn(*mut SubmitOperationBase<R>),
+    /// The whole operation, released by `delete_fn`.
+    op: NonNull<()>,
+    delete_fn: unsafe fn(NonNull<()>),
+}
+
+impl<R> SubmitOperationBase<R> {
+    unsafe fn delete(this: NonNull<Self>) {
+        let Self { op, delete_fn, .. } = *this.as_ptr();
+        delete_fn(op);
+    }
 }
 
-#[repr(C)]
-struct SubmitOperation<S: Sender<SubmitReceiver<R>>, R> {
+struct SubmitOperation<S: Sender<SubmitReceiver<R>>, R, A> {
     base: SubmitOperationBase<R>,
     op_state: Option<S::Operation>,
+    allocator: ManuallyDrop<A>,
 }
 
-impl<S, R> SubmitOperation<S, R>
+impl<S, R, A> SubmitOperation<S, R, A>
 where
     S: Sender<SubmitReceiver<R>>,
+    A: Allocator,
 {
-    fn new(sender: S, receiver: R) -> Box<Self> {
-        let mut op = Box::new(Self {
-            base: SubmitOperationBase {
-                receiver: UnsafeCell::new(Some(receiver)),
-                delete_fn: |op| {
-                    unsafe {
-                        let _ = Box::from_raw(op as *mut Self);
-                    };
+    fn new(sender: S, receiver: R, allocator: A) -> NonNull<Self> {
+        let op = allocator.allocate(Layout::new::<Self>()).cast::<Self>();
+        unsafe {
+            op.as_ptr().write(Self {
+                base: SubmitOperationBase {
+                    receiver: UnsafeCell::new(Some(receiver)),
+                    op: op.cast(),
+                    delete_fn: Self::delete,
                 },
-            },
-            op_state: None,
-        });
-        op.op_state = Some(sender.connect(SubmitReceiver {
-            op_state: NonNull::from(&op.base),
-        }));
+                op_state: None,
+                allocator: ManuallyDrop::new(allocator),
+            });
+
+            // Released if `connect` panics, it is only counted once
+            // connected.
+            let guard = scopeguard::guard(op, |op| Self::release(op));
+            let receiver = SubmitReceiver {
+                op_state: NonNull::from(&(*op.as_ptr()).base),
+            };
+            (*op.as_ptr()).op_state = Some(sender.connect(receiver));
+            scopeguard::ScopeGuard::into_inner(guard);
+        }
+        LIVE_OPERATIONS.fetch_add(1, Ordering::Relaxed);
 
         op
     }
+
+    unsafe fn delete(op: NonNull<()>) {
+        Self::release(op.cast());
+        LIVE_OPERATIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    unsafe fn release(op: NonNull<Self>) {
+        let allocator = ManuallyDrop::take(&mut (*op.as_ptr()).allocator);
+        ptr::drop_in_place(op.as_ptr());
+        allocator.deallocate(op.cast(), Layout::new::<Self>());
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::factories::just;
+    use exec_core::allocator::SlabAllocator;
+    use exec_core::Scheduler;
+    use exec_executor::RunLoop;
     use exec_test::receivers::ExpectValueReceiver;
+    use std::panic::{self, AssertUnwindSafe};
 
     #[test]
     fn test_submit() {
@@ -95,4 +209,75 @@ mod tests {
         let receiver = ExpectValueReceiver::new(42);
         submit(just_sender, receiver);
     }
+
+    #[test]
+    fn test_submit_with_allocator() {
+        let allocator = SlabAllocator::new();
+        let run_loop = RunLoop::new();
+        let mut scheduler = run_loop.get_scheduler();
+
+        for _ in 0..3 {
+            let receiver = ExpectValueReceiver::new(());
+            submit_with_allocator(scheduler.schedule(), receiver, allocator.clone());
+        }
+        assert_eq!(allocator.live(), 3);
+        assert!(live_operations() >= 3);
+
+        run_loop.finish();
+        run_loop.run();
+        assert_eq!((allocator.live(), allocator.pooled()), (0, 3));
+
+        // The pooled blocks are reused for operations of the same type.
+        let receiver = ExpectValueReceiver::new(());
+        submit_with_allocator(scheduler.schedule(), receiver, allocator.clone());
+        assert_eq!((allocator.live(), allocator.pooled()), (1, 2));
+        run_loop.run();
+        assert_eq!((allocator.live(), allocator.pooled()), (0, 3));
+    }
+
+    #[test]
+    fn test_submit_reuses_blocks() {
+        let run_loop = RunLoop::new();
+        let mut scheduler = run_loop.get_scheduler();
+
+        submit(scheduler.schedule(), ExpectValueReceiver::new(()));
+        run_loop.finish();
+        run_loop.run();
+        let cached = SmallBlocks::cached();
+        assert!(cached > 0);
+
+        // Taken from the cache of the thread rather than allocated.
+        submit(scheduler.schedule(), ExpectValueReceiver::new(()));
+        assert_eq!(SmallBlocks::cached(), cached - 1);
+        run_loop.run();
+        assert_eq!(SmallBlocks::cached(), cached);
+    }
+
+    struct PanicOnConnect;
+
+    impl<R> Sender<R> for PanicOnConnect {
+        type Value = ();
+        type Error = ();
+
+        type Operation = PanicOnConnect;
+
+        fn connect(self, _receiver: R) -> Self::Operation {
+            panic!("connect");
+        }
+    }
+
+    impl OperationState for PanicOnConnect {
+        fn start(&mut self) {}
+    }
+
+    #[test]
+    fn test_submit_connect_panics() {
+        let allocator = SlabAllocator::new();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let receiver = ExpectValueReceiver::new(());
+            submit_with_allocator(PanicOnConnect, receiver, allocator.clone());
+        }));
+        assert!(result.is_err());
+        assert_eq!((allocator.live(), allocator.pooled()), (0, 1));
+    }
 }
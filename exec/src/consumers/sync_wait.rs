@@ -1,39 +1,112 @@
-use exec_core::receiver::{SetError, SetStopped, SetValue};
-use exec_core::{OperationState, Sender};
-use exec_executor::RunLoop;
+use exec_core::panic::{self, Payload};
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Sender, StopSource, StopToken};
+use exec_executor::{RunLoop, RunLoopScheduler};
 use std::cell::UnsafeCell;
 use std::error::Error;
-use std::ptr::NonNull;
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::panic::resume_unwind;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 struct State<V, E> {
-    value: UnsafeCell<Option<WaitResult<V, E>>>,
+    value: UnsafeCell<Option<Outcome<V, E>>>,
+    done: AtomicBool,
+    stop_source: StopSource,
 }
 
 impl<V, E> State<V, E> {
     fn new() -> Self {
         Self {
             value: UnsafeCell::new(None),
+            done: AtomicBool::new(false),
+            stop_source: StopSource::new(),
         }
     }
+
+    fn is_done(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
 }
 
-#[derive(Debug)]
+/// A completion of a sender reified as a value, see
+/// [`materialize`](crate::adaptors::materialize).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WaitResult<V, E> {
     Value(V),
     Error(E),
     Stopped,
 }
 
+impl<V, E> WaitResult<V, E> {
+    /// Completes `receiver` with the completion it holds.
+    pub fn complete<R>(self, receiver: R)
+    where
+        R: SetValue<Value = V> + SetError<Error = E> + SetStopped,
+    {
+        match self {
+            WaitResult::Value(value) => receiver.set_value(value),
+            WaitResult::Error(error) => receiver.set_error(error),
+            WaitResult::Stopped => receiver.set_stopped(),
+        }
+    }
+}
+
+/// What the waiting thread finds once the receiver is done with the state.
+enum Outcome<V, E> {
+    Completed(WaitResult<V, E>),
+    Panicked(Payload),
+}
+
 pub struct SyncWaitReceiver<V, E> {
     state: NonNull<State<V, E>>,
-    run_loop: NonNull<RunLoop>,
+    run_loop: RunLoop,
 }
 
+// The waiting thread leaves the state alone until the receiver completes.
+unsafe impl<V: Send, E: Send> Send for SyncWaitReceiver<V, E> {}
+
 impl<V, E> SyncWaitReceiver<V, E> {
-    fn new(state: &State<V, E>, run_loop: &RunLoop) -> Self {
+    fn new(state: &State<V, E>, run_loop: RunLoop) -> Self {
         Self {
             state: NonNull::from(state),
-            run_loop: NonNull::from(run_loop),
+            run_loop,
+        }
+    }
+
+    fn complete(self, result: WaitResult<V, E>) {
+        let this = ManuallyDrop::new(self);
+        let run_loop = unsafe { ptr::read(&this.run_loop) };
+        unsafe { Self::complete_raw(this.state.cast(), run_loop, Outcome::Completed(result)) };
+    }
+
+    unsafe fn complete_raw(state: NonNull<()>, run_loop: RunLoop, result: Outcome<V, E>) {
+        let state = state.cast::<State<V, E>>();
+        let _ = (*state.as_ref().value.get()).insert(result);
+        // The waiting thread may return as soon as it sees the flag, the
+        // state must not be touched afterwards.
+        state.as_ref().done.store(true, Ordering::Release);
+        run_loop.notify();
+    }
+
+    unsafe fn panicked(state: NonNull<()>, run_loop: RunLoop, payload: Payload) {
+        Self::complete_raw(state, run_loop, Outcome::Panicked(payload));
+    }
+}
+
+impl<V, E> Drop for SyncWaitReceiver<V, E> {
+    /// Dropped by a panic, the waiting thread resumes it. It catches the
+    /// panic itself if it unwinds through it, so the state outlives the
+    /// handler.
+    fn drop(&mut self) {
+        if thread::panicking() {
+            let state = self.state.cast::<()>();
+            let run_loop = self.run_loop.clone();
+            let panicked: unsafe fn(NonNull<()>, RunLoop, Payload) = Self::panicked;
+            panic::on_unwind(move |payload| unsafe { panicked(state, run_loop, payload) });
         }
     }
 }
@@ -42,10 +115,7 @@ impl<V, E> SetValue for SyncWaitReceiver<V, E> {
     type Value = V;
 
     fn set_value(self, value: Self::Value) {
-        unsafe {
-            let _ = (*self.state.as_ref().value.get()).insert(WaitResult::Value(value));
-            self.run_loop.as_ref().finish();
-        }
+        self.complete(WaitResult::Value(value));
     }
 }
 
@@ -53,41 +123,161 @@ impl<V, E: Error> SetError for SyncWaitReceiver<V, E> {
     type Error = E;
 
     fn set_error(self, error: Self::Error) {
-        unsafe {
-            let _ = (*self.state.as_ref().value.get()).insert(WaitResult::Error(error));
-            self.run_loop.as_ref().finish();
-        }
+        self.complete(WaitResult::Error(error));
     }
 }
 
 impl<V, E> SetStopped for SyncWaitReceiver<V, E> {
     fn set_stopped(self) {
-        unsafe {
-            let _ = (*self.state.as_ref().value.get()).insert(WaitResult::Stopped);
-            self.run_loop.as_ref().finish();
+        self.complete(WaitResult::Stopped);
+    }
+}
+
+impl<V, E> GetStopToken for SyncWaitReceiver<V, E> {
+    fn get_stop_token(&self) -> StopToken {
+        unsafe { self.state.as_ref().stop_source.token() }
+    }
+}
+
+impl<V, E> GetScheduler for SyncWaitReceiver<V, E> {
+    type Scheduler = RunLoopScheduler;
+
+    fn get_scheduler(&self) -> Self::Scheduler {
+        self.run_loop.get_scheduler()
+    }
+}
+
+/// Error returned by [`sync_wait_for`] and [`sync_wait_until`].
+#[derive(Debug, PartialEq)]
+pub enum SyncWaitError<E> {
+    /// The sender completed with an error.
+    Error(E),
+    /// The deadline passed and the sender stopped after being asked to.
+    TimedOut,
+}
+
+impl<E: fmt::Display> fmt::Display for SyncWaitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncWaitError::Error(error) => error.fmt(f),
+            SyncWaitError::TimedOut => f.write_str("sync_wait timed out"),
         }
     }
 }
 
+impl<E: Error> Error for SyncWaitError<E> {}
+
+/// Blocks the current thread until the sender completes.
+///
+/// When called from a task running on a [`RunLoop`] (for example inside a
+/// `SingleThreadContext`), the current loop keeps executing its queued work
+/// while waiting, so senders scheduled on the very same context can make
+/// progress instead of deadlocking.
+///
+/// A panic unwinding through the receiver, on whichever thread, resumes on
+/// the waiting thread. A panic of other work run by the loop while waiting
+/// resumes once the sender completed.
 pub fn sync_wait<S, V, E>(sender: S) -> Result<Option<V>, E>
 where
     S: Sender<SyncWaitReceiver<V, E>, Value = V, Error = E>,
 {
-    let run_loop = RunLoop::new();
+    let run_loop = RunLoop::current().unwrap_or_default();
     let mut state = State::new();
 
     // Launch the sender with a continuation that will fill in a variant
-    // and notify a condition variable.
-    let mut op = sender.connect(SyncWaitReceiver::new(&state, &run_loop));
-    op.start();
+    // and wake up the run loop.
+    // Leaked when unwinding, the receiver would otherwise be dropped by the
+    // panic and expect the state to outlive it.
+    let mut op = ManuallyDrop::new(sender.connect(SyncWaitReceiver::new(&state, run_loop.clone())));
+    let mut unhandled = None;
+    run_guarded(&mut unhandled, || op.start());
 
     // Wait for the variant to be filled in.
-    run_loop.run();
+    while run_guarded(&mut unhandled, || run_loop.run_until(|| state.is_done())).is_none() {}
+
+    unsafe { ManuallyDrop::drop(&mut op) };
+    if let Some(payload) = unhandled {
+        resume_unwind(payload);
+    }
+
+    match state.value.get_mut().take().unwrap() {
+        Outcome::Completed(WaitResult::Value(v)) => Ok(Some(v)),
+        Outcome::Completed(WaitResult::Error(e)) => Err(e),
+        Outcome::Completed(WaitResult::Stopped) => Ok(None),
+        Outcome::Panicked(payload) => resume_unwind(payload),
+    }
+}
+
+/// Blocks the current thread until the sender completes or `timeout` elapses.
+///
+/// See [`sync_wait_until`].
+pub fn sync_wait_for<S, V, E>(sender: S, timeout: Duration) -> Result<Option<V>, SyncWaitError<E>>
+where
+    S: Sender<SyncWaitReceiver<V, E>, Value = V, Error = E>,
+{
+    sync_wait_until(sender, Instant::now() + timeout)
+}
+
+/// Blocks the current thread until the sender completes or `deadline` passes.
+///
+/// When the deadline passes, stop is requested through the stop token of the
+/// receiver and the call keeps waiting for the sender to complete. A stopped
+/// completion is then reported as [`SyncWaitError::TimedOut`], a sender that
+/// still manages to complete with a value or an error reports it as usual.
+pub fn sync_wait_until<S, V, E>(sender: S, deadline: Instant) -> Result<Option<V>, SyncWaitError<E>>
+where
+    S: Sender<SyncWaitReceiver<V, E>, Value = V, Error = E>,
+{
+    let run_loop = RunLoop::current().unwrap_or_default();
+    let mut state = State::new();
+
+    let mut op = ManuallyDrop::new(sender.connect(SyncWaitReceiver::new(&state, run_loop.clone())));
+    let mut unhandled = None;
+    run_guarded(&mut unhandled, || op.start());
+
+    let timed_out = loop {
+        let done = run_guarded(&mut unhandled, || {
+            run_loop.run_until_deadline(|| state.is_done(), deadline)
+        });
+        if let Some(done) = done {
+            break !done;
+        }
+    };
+    if timed_out {
+        state.stop_source.request_stop();
+        while run_guarded(&mut unhandled, || run_loop.run_until(|| state.is_done())).is_none() {}
+    }
+
+    unsafe { ManuallyDrop::drop(&mut op) };
+    if let Some(payload) = unhandled {
+        resume_unwind(payload);
+    }
 
     match state.value.get_mut().take().unwrap() {
-        WaitResult::Value(v) => Ok(Some(v)),
-        WaitResult::Error(e) => Err(e),
-        WaitResult::Stopped => Ok(None),
+        Outcome::Completed(WaitResult::Value(v)) => Ok(Some(v)),
+        Outcome::Completed(WaitResult::Error(e)) => Err(SyncWaitError::Error(e)),
+        Outcome::Completed(WaitResult::Stopped) if timed_out => Err(SyncWaitError::TimedOut),
+        Outcome::Completed(WaitResult::Stopped) => Ok(None),
+        Outcome::Panicked(payload) => resume_unwind(payload),
+    }
+}
+
+/// Runs `f` on the waiting thread. A panic is delivered to the receivers it
+/// unwound through, which may be the one of the wait itself. Returns `None`
+/// if `f` panicked.
+///
+/// A panic nobody takes over is kept in `unhandled`, it is resumed once the
+/// sender completed and its operation has been dropped: the operation and the
+/// state it points to must outlive anything that may still reach them, like
+/// the tasks queued on the run loop or the stop callbacks.
+fn run_guarded<T>(unhandled: &mut Option<Payload>, f: impl FnOnce() -> T) -> Option<T> {
+    match panic::catch_unwind(f) {
+        Ok(value) => Some(value),
+        Err(None) => None,
+        Err(Some(payload)) => {
+            unhandled.get_or_insert(payload);
+            None
+        }
     }
 }
 
@@ -95,7 +285,13 @@ where
 mod tests {
     use super::*;
     use crate::adaptors::then;
+    use crate::consumers::start_detached;
     use crate::factories::just;
+    use exec_core::Scheduler;
+    use exec_executor::{QueueKind, SingleThreadContext};
+    use exec_test::senders::NeverSender;
+    use std::panic::AssertUnwindSafe;
+    use std::sync::{mpsc, Arc};
 
     #[test]
     fn test_sync_wait() {
@@ -103,4 +299,77 @@ mod tests {
         let sender = then(sender, |v| v + 1);
         println!("{:?}", sync_wait(sender));
     }
+
+    #[test]
+    fn test_sync_wait_on_context_thread() {
+        for kind in [QueueKind::Mutex, QueueKind::LockFree] {
+            let context = SingleThreadContext::with_queue(kind);
+            let mut scheduler = context.get_scheduler();
+            let (tx, rx) = mpsc::channel();
+
+            let mut inner = scheduler.clone();
+            start_detached(then(scheduler.schedule(), move |_| {
+                // Needs the context we are running on to make progress.
+                let result = sync_wait(then(inner.schedule(), |_| 42));
+                tx.send(result.unwrap()).unwrap();
+            }));
+
+            assert_eq!(rx.recv().unwrap(), Some(42));
+        }
+    }
+
+    #[test]
+    fn test_sync_wait_resumes_panic() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+
+        // The panic unwinds through the receiver on the context thread.
+        let sender = then(scheduler.schedule(), |_| -> i32 { panic!("boom") });
+        let payload = std::panic::catch_unwind(AssertUnwindSafe(|| sync_wait(sender))).unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+
+        let sender = then(scheduler.schedule(), |_| 42);
+        assert_eq!(sync_wait(sender), Ok(Some(42)));
+    }
+
+    #[test]
+    fn test_sync_wait_resumes_other_panic_once_complete() {
+        let run_loop = RunLoop::new();
+        let mut scheduler = run_loop.get_scheduler();
+        let completed = Arc::new(AtomicBool::new(false));
+
+        let mut inner = scheduler.clone();
+        let flag = completed.clone();
+        start_detached(then(scheduler.schedule(), move |_| {
+            // Run by the loop of the nested wait before its own sender.
+            start_detached(then(inner.schedule(), |_| panic!("other")));
+            let sender = then(inner.schedule(), move |_| {
+                flag.store(true, Ordering::SeqCst)
+            });
+            sync_wait(sender).unwrap();
+        }));
+
+        let payload = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            run_loop.run_until(|| completed.load(Ordering::SeqCst))
+        }))
+        .unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"other"));
+        assert!(completed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_sync_wait_for() {
+        let result = sync_wait_for(just(42), Duration::from_secs(1));
+        assert_eq!(result, Ok::<_, SyncWaitError<()>>(Some(42)));
+    }
+
+    #[test]
+    fn test_sync_wait_for_timeout() {
+        let result = sync_wait_for(NeverSender::<i32>::new(), Duration::from_millis(10));
+        assert_eq!(result, Err::<_, SyncWaitError<()>>(SyncWaitError::TimedOut));
+
+        let sender = then(NeverSender::<i32>::new(), |v| v + 1);
+        let result = sync_wait_for(sender, Duration::from_millis(10));
+        assert_eq!(result, Err::<_, SyncWaitError<()>>(SyncWaitError::TimedOut));
+    }
 }
@@ -0,0 +1,75 @@
+use crate::consumers::{IntoAwaitable, SenderAwaitable};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+/// Spawns a sender as a task on the Tokio runtime the caller runs on.
+///
+/// The output of the task is the one of [`IntoAwaitable`]. Aborting the task
+/// requests stop on the sender.
+///
+/// Panics when called outside of a Tokio runtime.
+pub fn spawn_tokio<S, V, E>(sender: S) -> JoinHandle<Result<Option<V>, E>>
+where
+    S: IntoAwaitable<V, E>,
+    SenderAwaitable<S, V, E>: Send + 'static,
+    V: Send + 'static,
+    E: Send + 'static,
+{
+    spawn_tokio_on(&Handle::current(), sender)
+}
+
+/// Spawns a sender as a task on the Tokio runtime of `handle`.
+pub fn spawn_tokio_on<S, V, E>(handle: &Handle, sender: S) -> JoinHandle<Result<Option<V>, E>>
+where
+    S: IntoAwaitable<V, E>,
+    SenderAwaitable<S, V, E>: Send + 'static,
+    V: Send + 'static,
+    E: Send + 'static,
+{
+    handle.spawn(sender.into_awaitable())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{just, then};
+    use exec_core::Scheduler;
+    use exec_executor::TokioScheduler;
+    use exec_test::senders::NeverSender;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use tokio::runtime::Builder;
+
+    #[test]
+    fn test_spawn_tokio() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let handle = spawn_tokio_on(runtime.handle(), then(just(1), |x| x + 1));
+        assert_eq!(runtime.block_on(handle).unwrap(), Ok(Some(2)));
+    }
+
+    #[test]
+    fn test_spawn_tokio_on_tokio_scheduler() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let id = runtime.block_on(async {
+            let mut scheduler = TokioScheduler::current();
+            let sender = then(scheduler.schedule(), |_| thread::current().id());
+            spawn_tokio(sender).await.unwrap()
+        });
+        assert_eq!(id, Ok(Some(thread::current().id())));
+    }
+
+    #[test]
+    fn test_spawn_tokio_abort_requests_stop() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let sender = NeverSender::<i32>::new();
+        let stopped = sender.stopped();
+
+        let handle = spawn_tokio_on(runtime.handle(), sender);
+        // Let the task start the sender before aborting it.
+        runtime.block_on(tokio::task::yield_now());
+        assert!(!stopped.load(Ordering::SeqCst));
+        handle.abort();
+        assert!(runtime.block_on(handle).unwrap_err().is_cancelled());
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+}
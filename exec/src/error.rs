@@ -0,0 +1,146 @@
+use crate::adaptors::TimedOut;
+use exec_core::panic::PanicError;
+use std::any::Any;
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::Deref;
+
+/// A type-erased error, the common error channel of heterogeneous pipelines.
+///
+/// Any [`std::error::Error`] is widened into it with [`Error::new`], or
+/// [`box_error`](crate::box_error) for the error channel of a sender, so that
+/// stages with different error types can complete into one receiver. The
+/// errors of this crate convert into it with `From`, e.g. in
+/// [`then_try`](crate::then_try) or with `?` in a [`Task`](crate::Task).
+///
+/// It implements `std::error::Error` itself, forwarding to the wrapped error,
+/// which rules out a blanket `From` conversion of all errors like
+/// `anyhow::Error` has. Widening is therefore not automatic everywhere: an
+/// adaptor converting with `Into`, like `then_try`, widens only the errors
+/// above, any other one needs wrapping with [`Error::new`] first, or the
+/// whole error channel with `box_error`.
+pub struct Error {
+    inner: Box<dyn StdError + Send + Sync + 'static>,
+}
+
+impl Error {
+    /// Wraps `error`, an `Error` is passed through as is.
+    pub fn new<E>(error: E) -> Self
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        let mut error = Some(error);
+        if let Some(error) = (&mut error as &mut dyn Any).downcast_mut::<Option<Self>>() {
+            return error.take().unwrap();
+        }
+        Self {
+            inner: Box::new(error.unwrap()),
+        }
+    }
+
+    /// An error carrying nothing but `message`.
+    pub fn msg<M>(message: M) -> Self
+    where
+        M: fmt::Display + fmt::Debug + Send + Sync + 'static,
+    {
+        Self::new(Message(message))
+    }
+
+    pub fn is<E: StdError + 'static>(&self) -> bool {
+        self.inner.is::<E>()
+    }
+
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        self.inner.downcast_ref()
+    }
+
+    pub fn downcast<E: StdError + 'static>(self) -> Result<E, Self> {
+        match self.inner.downcast() {
+            Ok(error) => Ok(*error),
+            Err(inner) => Err(Self { inner }),
+        }
+    }
+
+    pub fn into_inner(self) -> Box<dyn StdError + Send + Sync + 'static> {
+        self.inner
+    }
+}
+
+impl From<PanicError> for Error {
+    fn from(error: PanicError) -> Self {
+        Self::new(error)
+    }
+}
+
+impl From<TimedOut> for Error {
+    fn from(error: TimedOut) -> Self {
+        Self::new(error)
+    }
+}
+
+impl From<Box<dyn StdError + Send + Sync + 'static>> for Error {
+    fn from(inner: Box<dyn StdError + Send + Sync + 'static>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Deref for Error {
+    type Target = dyn StdError + Send + Sync + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.inner
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.inner.source()
+    }
+}
+
+struct Message<M>(M);
+
+impl<M: fmt::Debug> fmt::Debug for Message<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<M: fmt::Display> fmt::Display for Message<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<M: fmt::Display + fmt::Debug> StdError for Message<M> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exec_test::errors::TestError;
+
+    #[test]
+    fn test_error() {
+        let error = Error::new(TestError);
+        assert!(error.is::<TestError>());
+        assert!(Error::new(Error::new(TestError)).is::<TestError>());
+        assert_eq!(error.to_string(), TestError.to_string());
+        assert_eq!(error.downcast::<TestError>().unwrap(), TestError);
+
+        let error = Error::msg("boom");
+        assert_eq!(error.to_string(), "boom");
+        assert!(error.downcast_ref::<TestError>().is_none());
+    }
+}
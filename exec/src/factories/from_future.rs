@@ -0,0 +1,391 @@
+use crate::consumers::submit;
+use crate::consumers::submit::SubmitReceiver;
+use crate::consumers::with_stop_token;
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{OperationState, Scheduler, Sender, StopCallback, StopToken};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+/// Turns a future into a sender.
+///
+/// The future is polled on the scheduler of the receiver, its waker schedules
+/// another poll on the same scheduler. `Ok` and `Err` outputs complete the
+/// value and error channels. Stop requested through the receiver's stop token
+/// drops the future and completes with stopped.
+pub fn from_future<F>(future: F) -> FromFuture<F> {
+    FromFuture::new(future)
+}
+
+/// Like [`from_future`], with the future polled on `scheduler`.
+pub fn from_future_on<Sch, F>(scheduler: Sch, future: F) -> FromFutureOn<Sch, F> {
+    FromFutureOn::new(scheduler, future)
+}
+
+pub struct FromFuture<F> {
+    future: F,
+}
+
+impl<F> FromFuture<F> {
+    pub fn new(future: F) -> Self {
+        Self { future }
+    }
+}
+
+pub struct FromFutureOn<Sch, F> {
+    scheduler: Sch,
+    future: F,
+}
+
+impl<Sch, F> FromFutureOn<Sch, F> {
+    pub fn new(scheduler: Sch, future: F) -> Self {
+        Self { scheduler, future }
+    }
+}
+
+struct Shared<Sch, F, R> {
+    scheduler: Mutex<Sch>,
+    /// Submits a poll to `scheduler`. Stored as a function pointer so that
+    /// the receivers below don't need to repeat the scheduler bounds, which
+    /// would make them recursive.
+    schedule: fn(&Arc<Self>),
+    state: Mutex<State<F, R>>,
+    /// A poll has been submitted to the scheduler and has not started yet.
+    scheduled: AtomicBool,
+    stop_requested: AtomicBool,
+}
+
+struct State<F, R> {
+    /// The future and the receiver it completes. Taken out of the lock while
+    /// the future is polled, so that a waker scheduling inline or a panic
+    /// doesn't find the lock held.
+    polled: Option<(Pin<Box<F>>, R)>,
+    polling: bool,
+    /// Woken up while being polled, the future is polled again right away.
+    woken: bool,
+    /// Stop token of the receiver, senders awaited by the future observe it.
+    stop_token: StopToken,
+    stop_callback: Option<StopCallback>,
+}
+
+enum Completion<T, E> {
+    Value(T),
+    Error(E),
+    Stopped,
+}
+
+fn schedule_poll<Sch, F, R>(shared: &Arc<Shared<Sch, F, R>>)
+where
+    Sch: Scheduler<SubmitReceiver<PollReceiver<Sch, F, R>>>,
+{
+    let sender = shared.scheduler.lock().unwrap().schedule();
+    submit(
+        sender,
+        PollReceiver {
+            shared: shared.clone(),
+        },
+    );
+}
+
+impl<Sch, F, T, E, R> Shared<Sch, F, R>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + Send + 'static,
+    Sch: Send + 'static,
+{
+    fn schedule(self: &Arc<Self>) {
+        if !self.scheduled.swap(true, Ordering::AcqRel) {
+            (self.schedule)(self);
+        }
+    }
+
+    fn poll(self: &Arc<Self>) {
+        self.scheduled.store(false, Ordering::Release);
+
+        let ((mut future, receiver), stop_token) = {
+            let mut state = self.state.lock().unwrap();
+            if state.polling {
+                state.woken = true;
+                return;
+            }
+            let Some(polled) = state.polled.take() else {
+                return;
+            };
+            state.polling = true;
+            (polled, state.stop_token.clone())
+        };
+        // Unwinding drops the receiver with the future, the stop callback
+        // must not keep the state alive past that.
+        let _unwinding = scopeguard::guard_on_unwind((), |()| {
+            let stop_callback = self.state.lock().unwrap().stop_callback.take();
+            drop(stop_callback);
+        });
+
+        let waker = Waker::from(self.clone());
+        let completion = loop {
+            if self.stop_requested.load(Ordering::Acquire) {
+                break Completion::Stopped;
+            }
+            let poll = with_stop_token(&stop_token, || {
+                future.as_mut().poll(&mut Context::from_waker(&waker))
+            });
+            match poll {
+                Poll::Ready(Ok(value)) => break Completion::Value(value),
+                Poll::Ready(Err(error)) => break Completion::Error(error),
+                Poll::Pending => {
+                    let mut state = self.state.lock().unwrap();
+                    if !std::mem::take(&mut state.woken) {
+                        state.polling = false;
+                        state.polled = Some((future, receiver));
+                        return;
+                    }
+                }
+            }
+        };
+        drop(future);
+
+        self.complete(receiver, completion);
+    }
+
+    /// Completes with stopped once the scheduler can't poll anymore.
+    fn abandon(&self) {
+        let polled = {
+            let mut state = self.state.lock().unwrap();
+            if state.polling {
+                // Completed by the ongoing poll.
+                self.stop_requested.store(true, Ordering::Release);
+                state.woken = true;
+                return;
+            }
+            state.polled.take()
+        };
+        if let Some((future, receiver)) = polled {
+            drop(future);
+            self.complete(receiver, Completion::Stopped);
+        }
+    }
+
+    fn complete(&self, receiver: R, completion: Completion<T, E>) {
+        let stop_callback = self.state.lock().unwrap().stop_callback.take();
+        drop(stop_callback);
+
+        match completion {
+            Completion::Value(value) => receiver.set_value(value),
+            Completion::Error(error) => receiver.set_error(error),
+            Completion::Stopped => receiver.set_stopped(),
+        }
+    }
+}
+
+impl<Sch, F, T, E, R> Wake for Shared<Sch, F, R>
+where
+    Sch: Send + 'static,
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + Send + 'static,
+{
+    fn wake(self: Arc<Self>) {
+        self.schedule();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.schedule();
+    }
+}
+
+/// Receives the go-ahead of the scheduler to poll the future.
+pub struct PollReceiver<Sch, F, R> {
+    shared: Arc<Shared<Sch, F, R>>,
+}
+
+impl<Sch, F, T, E, R> SetValue for PollReceiver<Sch, F, R>
+where
+    Sch: Send + 'static,
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + Send + 'static,
+{
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.shared.poll();
+    }
+}
+
+impl<Sch, F, T, E, R> SetStopped for PollReceiver<Sch, F, R>
+where
+    Sch: Send + 'static,
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + Send + 'static,
+{
+    fn set_stopped(self) {
+        // The scheduler is gone, the future cannot make progress anymore.
+        self.shared.abandon();
+    }
+}
+
+pub struct FromFutureOperation<Sch, F, R> {
+    shared: Arc<Shared<Sch, F, R>>,
+}
+
+impl<Sch, F, T, E, R> FromFutureOperation<Sch, F, R>
+where
+    Sch: Scheduler<SubmitReceiver<PollReceiver<Sch, F, R>>> + 'static,
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + GetStopToken + Send + 'static,
+{
+    fn new(scheduler: Sch, future: F, receiver: R) -> Self {
+        let stop_token = receiver.get_stop_token();
+        Self {
+            shared: Arc::new(Shared {
+                scheduler: Mutex::new(scheduler),
+                schedule: schedule_poll::<Sch, F, R>,
+                state: Mutex::new(State {
+                    polled: Some((Box::pin(future), receiver)),
+                    polling: false,
+                    woken: false,
+                    stop_token,
+                    stop_callback: None,
+                }),
+                scheduled: AtomicBool::new(false),
+                stop_requested: AtomicBool::new(false),
+            }),
+        }
+    }
+}
+
+impl<Sch, F, T, E, R> OperationState for FromFutureOperation<Sch, F, R>
+where
+    Sch: Send + 'static,
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + Send + 'static,
+{
+    fn start(&mut self) {
+        let token = self.shared.state.lock().unwrap().stop_token.clone();
+
+        let shared = self.shared.clone();
+        let stop_callback = StopCallback::new(&token, move || {
+            shared.stop_requested.store(true, Ordering::Release);
+            shared.schedule();
+        });
+
+        let mut state = self.shared.state.lock().unwrap();
+        if state.polled.is_some() || state.polling {
+            state.stop_callback = Some(stop_callback);
+        }
+        drop(state);
+
+        self.shared.schedule();
+    }
+}
+
+impl<F, T, E, R> Sender<R> for FromFuture<F>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + GetStopToken + Send + 'static,
+    R: GetScheduler,
+    R::Scheduler: Scheduler<SubmitReceiver<PollReceiver<R::Scheduler, F, R>>> + 'static,
+{
+    type Value = T;
+    type Error = E;
+
+    type Operation = FromFutureOperation<R::Scheduler, F, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        FromFutureOperation::new(receiver.get_scheduler(), self.future, receiver)
+    }
+}
+
+impl<Sch, F, T, E, R> Sender<R> for FromFutureOn<Sch, F>
+where
+    Sch: Scheduler<SubmitReceiver<PollReceiver<Sch, F, R>>> + 'static,
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    R: SetValue<Value = T> + SetError<Error = E> + SetStopped + GetStopToken + Send + 'static,
+{
+    type Value = T;
+    type Error = E;
+
+    type Operation = FromFutureOperation<Sch, F, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        FromFutureOperation::new(self.scheduler, self.future, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumers::{sync_wait, sync_wait_for, SyncWaitError};
+    use exec_executor::{SingleThreadContext, TrampolineScheduler};
+    use exec_test::errors::TestError;
+    use futures::channel::oneshot;
+    use std::convert::Infallible;
+    use std::future;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_from_future() {
+        // Polled on the run loop of `sync_wait`.
+        let sender = from_future(async { Ok::<_, Infallible>(thread::current().id()) });
+        let id = sync_wait(sender).unwrap().unwrap();
+        assert_eq!(id, thread::current().id());
+
+        let context = SingleThreadContext::new();
+        let sender = from_future_on(context.get_scheduler(), async {
+            Ok::<_, Infallible>(thread::current().id())
+        });
+        let id = sync_wait(sender).unwrap().unwrap();
+        assert_ne!(id, thread::current().id());
+    }
+
+    #[test]
+    fn test_from_future_error() {
+        let context = SingleThreadContext::new();
+        let sender = from_future_on(context.get_scheduler(), async { Err::<(), _>(TestError) });
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+
+    #[test]
+    fn test_from_future_wake_from_other_thread() {
+        let context = SingleThreadContext::new();
+        let (tx, rx) = oneshot::channel();
+        let sender = from_future_on(context.get_scheduler(), async move {
+            let value = rx.await.unwrap();
+            Ok::<_, Infallible>((value, thread::current().id()))
+        });
+
+        let worker = thread::spawn(move || tx.send(42).unwrap());
+        let (value, id) = sync_wait(sender).unwrap().unwrap();
+        assert_eq!(value, 42);
+        assert_ne!(id, worker.thread().id());
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn test_from_future_wake_while_polled() {
+        // The trampoline polls again inline, from within the poll.
+        let mut polls = 0;
+        let future = future::poll_fn(move |cx| {
+            polls += 1;
+            if polls < 3 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            Poll::Ready(Ok::<_, Infallible>(polls))
+        });
+        let sender = from_future_on(TrampolineScheduler::default(), future);
+        assert_eq!(sync_wait(sender), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_from_future_stopped() {
+        let context = SingleThreadContext::new();
+        let sender = from_future_on(
+            context.get_scheduler(),
+            futures::future::pending::<Result<(), Infallible>>(),
+        );
+        let result = sync_wait_for(sender, Duration::from_millis(10));
+        assert_eq!(result, Err(SyncWaitError::TimedOut));
+    }
+}
@@ -1,5 +1,9 @@
+mod from_future;
 mod just;
 mod just_error;
 
-pub use just::just;
-pub use just_error::just_error;
+pub use from_future::{
+    from_future, from_future_on, FromFuture, FromFutureOn, FromFutureOperation, PollReceiver,
+};
+pub use just::{just, Just};
+pub use just_error::{just_error, JustError};
@@ -1,10 +1,29 @@
-mod adaptors;
-pub use adaptors::then;
+pub mod adaptors;
 
-mod consumers;
-pub use consumers::start_detached;
+mod async_scope;
+pub use adaptors::{
+    box_error, catch_panic, dematerialize, finally, map_error, materialize, repeat_effect_until,
+    retry, retry_with_backoff, then, then_try, timeout, when_any, Backoff, Dematerialize, Finally,
+    MapError, Materialize, RepeatEffectUntil, Retry, RetryWithBackoff, ThenTry, TimedOut, Timeout,
+    WhenAny,
+};
+pub use async_scope::AsyncScope;
+
+pub mod consumers;
 pub use consumers::submit;
-pub use consumers::sync_wait;
+pub use consumers::IntoAwaitable;
+#[cfg(feature = "tokio")]
+pub use consumers::{spawn_tokio, spawn_tokio_on};
+pub use consumers::{start_detached, start_detached_with};
+pub use consumers::{sync_wait, sync_wait_for, sync_wait_until, WaitResult};
+
+mod error;
+pub use error::Error;
+
+pub mod factories;
+pub use factories::{from_future, from_future_on, just};
+
+pub mod sequence;
 
-mod factories;
-pub use factories::just;
+mod task;
+pub use task::Task;
@@ -0,0 +1,61 @@
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::{OperationState, SequenceSender};
+
+/// A sequence completing without any item.
+pub fn empty_sequence() -> EmptySequence {
+    EmptySequence
+}
+
+pub struct EmptySequence;
+
+pub struct EmptySequenceOperation<R> {
+    receiver: Option<R>,
+}
+
+impl<R> OperationState for EmptySequenceOperation<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn start(&mut self) {
+        if let Some(receiver) = self.receiver.take() {
+            if receiver.get_stop_token().stop_requested() {
+                receiver.set_stopped();
+            } else {
+                receiver.set_value(());
+            }
+        }
+    }
+}
+
+impl<R> SequenceSender<R> for EmptySequence
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Error = ();
+
+    type Operation = EmptySequenceOperation<R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        EmptySequenceOperation {
+            receiver: Some(receiver),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exec_test::receivers::{CollectReceiver, Collected};
+
+    #[test]
+    fn test_empty_sequence() {
+        let (receiver, collected) = CollectReceiver::<i32>::new();
+        empty_sequence().subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![]));
+
+        let (receiver, collected) = CollectReceiver::<i32>::new();
+        receiver.stop_source().request_stop();
+        empty_sequence().subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![]));
+    }
+}
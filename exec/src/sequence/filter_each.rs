@@ -0,0 +1,228 @@
+use super::utils::{complete, start_next};
+use crate::factories::{just, Just};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{Sender, SequenceSender, StopToken};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// Passes on the items of `sequence` whose value satisfies `predicate`, the
+/// others are consumed right away.
+pub fn filter_each<S, P, T>(sequence: S, predicate: P) -> FilterEach<S, P, T> {
+    FilterEach::new(sequence, predicate)
+}
+
+pub struct FilterEach<S, P, T> {
+    sequence: S,
+    predicate: P,
+    _phantom: PhantomData<T>,
+}
+
+impl<S, P, T> FilterEach<S, P, T> {
+    pub fn new(sequence: S, predicate: P) -> Self {
+        Self {
+            sequence,
+            predicate,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// The predicate and the downstream receiver, shared with the items which
+/// may complete concurrently.
+struct Filter<P, R> {
+    predicate: P,
+    receiver: Option<R>,
+}
+
+type SharedFilter<P, R> = Arc<Mutex<Filter<P, R>>>;
+
+pub struct FilterEachReceiver<P, R, T> {
+    filter: SharedFilter<P, R>,
+    _phantom: PhantomData<fn(T)>,
+}
+
+impl<P, R, T> FilterEachReceiver<P, R, T> {
+    fn take(self) -> R {
+        self.filter.lock().unwrap().receiver.take().unwrap()
+    }
+}
+
+impl<S, P, R, T> SetNext<S> for FilterEachReceiver<P, R, T>
+where
+    S: Sender<FilterItemReceiver<P, R, T>>,
+{
+    type Next = FilterNext<S, P, R, T>;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        FilterNext {
+            item,
+            filter: self.filter.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<P, R: SetValue, T> SetValue for FilterEachReceiver<P, R, T> {
+    type Value = R::Value;
+
+    fn set_value(self, value: Self::Value) {
+        self.take().set_value(value);
+    }
+}
+
+impl<P, R: SetError, T> SetError for FilterEachReceiver<P, R, T> {
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.take().set_error(error);
+    }
+}
+
+impl<P, R: SetStopped, T> SetStopped for FilterEachReceiver<P, R, T> {
+    fn set_stopped(self) {
+        self.take().set_stopped();
+    }
+}
+
+impl<P, R: GetStopToken, T> GetStopToken for FilterEachReceiver<P, R, T> {
+    fn get_stop_token(&self) -> StopToken {
+        let filter = self.filter.lock().unwrap();
+        filter.receiver.as_ref().unwrap().get_stop_token()
+    }
+}
+
+/// Runs an item, then passes its value on if it satisfies the predicate.
+pub struct FilterNext<S, P, R, T> {
+    item: S,
+    filter: SharedFilter<P, R>,
+    _phantom: PhantomData<fn(T)>,
+}
+
+impl<S, P, R, T> Sender<NextReceiver> for FilterNext<S, P, R, T>
+where
+    S: Sender<FilterItemReceiver<P, R, T>>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = S::Operation;
+
+    fn connect(self, receiver: NextReceiver) -> Self::Operation {
+        self.item.connect(FilterItemReceiver {
+            filter: self.filter,
+            receiver,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+pub struct FilterItemReceiver<P, R, T> {
+    filter: SharedFilter<P, R>,
+    receiver: NextReceiver,
+    _phantom: PhantomData<fn(T)>,
+}
+
+impl<P, R, T> SetValue for FilterItemReceiver<P, R, T>
+where
+    P: FnMut(&T) -> bool,
+    R: SetNext<Just<T>>,
+{
+    type Value = T;
+
+    fn set_value(self, value: Self::Value) {
+        let mut filter = self.filter.lock().unwrap();
+        if !(filter.predicate)(&value) {
+            drop(filter);
+            return self.receiver.set_value(());
+        }
+
+        let next = filter.receiver.as_mut().unwrap().set_next(just(value));
+        drop(filter);
+        let stop_token = self.receiver.get_stop_token();
+        start_next(next, stop_token, move |more| complete(self.receiver, more));
+    }
+}
+
+impl<P, R, T> SetStopped for FilterItemReceiver<P, R, T> {
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<P, R, T> GetStopToken for FilterItemReceiver<P, R, T> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<S, P, T, R> SequenceSender<R> for FilterEach<S, P, T>
+where
+    S: SequenceSender<FilterEachReceiver<P, R, T>>,
+{
+    type Error = S::Error;
+
+    type Operation = S::Operation;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        self.sequence.subscribe(FilterEachReceiver {
+            filter: Arc::new(Mutex::new(Filter {
+                predicate: self.predicate,
+                receiver: Some(receiver),
+            })),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then;
+    use crate::sequence::{interval, iterate, merge, take, transform_each};
+    use exec_core::OperationState;
+    use exec_executor::SingleThreadContext;
+    use exec_test::receivers::{CollectReceiver, Collected};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_filter_each() {
+        let sequence = filter_each(iterate(1..=6), |x: &i32| x % 2 == 0);
+        let (receiver, collected) = CollectReceiver::new();
+        sequence.subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![2, 4, 6]));
+
+        // Skipped items don't count towards the limit of the consumer.
+        let sequence = filter_each(iterate(1..), |x: &i32| x % 3 == 0);
+        let (receiver, collected) = CollectReceiver::with_limit(2);
+        sequence.subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![3, 6]));
+    }
+
+    #[test]
+    fn test_filter_each_concurrent_items() {
+        // Items of the merged sequences complete on both context threads.
+        let contexts = [SingleThreadContext::new(), SingleThreadContext::new()];
+        let ticks = |context: &SingleThreadContext, value: u32| {
+            let ticks = interval(context.get_scheduler(), Duration::from_millis(1));
+            let ticks = transform_each(ticks, move |tick| then(tick, move |()| value));
+            take(ticks, 20)
+        };
+        let calls = Arc::new(AtomicUsize::new(0));
+        let predicate = {
+            let calls = calls.clone();
+            move |value: &u32| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                value.is_multiple_of(2)
+            }
+        };
+
+        let sequence = merge([ticks(&contexts[0], 1), ticks(&contexts[1], 2)], 2);
+        let (receiver, collected) = CollectReceiver::new();
+        let mut op = filter_each(sequence, predicate).subscribe(receiver);
+        op.start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![2; 20]));
+        assert_eq!(calls.load(Ordering::SeqCst), 40);
+    }
+}
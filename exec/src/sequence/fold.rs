@@ -0,0 +1,328 @@
+use super::utils::{forward_error, link_stop, ForwardError};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{OperationState, Sender, SequenceSender, StopCallback, StopSource, StopToken};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// Reduces the values of `sequence` with `func`, starting from `init`, and
+/// completes with the result once the sequence ends.
+///
+/// A stopped sequence completes stopped. An item completing with an error
+/// stops the sequence, the fold then completes with the error. The error type
+/// is the one of the items, it can't be inferred from a sequence whose items
+/// never fail, e.g. `sync_wait::<_, _, Infallible>(fold(...))`.
+pub fn fold<S, A, F, V>(sequence: S, init: A, func: F) -> Fold<S, A, F, V>
+where
+    F: FnMut(A, V) -> A,
+{
+    Fold::new(sequence, init, func)
+}
+
+/// Collects the values of `sequence`, e.g. `collect::<Vec<_>, _, _>(sequence)`.
+pub fn collect<C, S, V>(sequence: S) -> Collect<S, C, V>
+where
+    C: Default + Extend<V>,
+{
+    Fold::new(sequence, C::default(), extend_one::<C, V>)
+}
+
+/// Completes with `()` once `sequence` ends, dropping its values.
+pub fn ignore_all_values<S, V>(sequence: S) -> IgnoreAllValues<S, V> {
+    Fold::new(sequence, (), ignore::<V>)
+}
+
+pub type Collect<S, C, V> = Fold<S, C, fn(C, V) -> C, V>;
+
+pub type IgnoreAllValues<S, V> = Fold<S, (), fn((), V), V>;
+
+fn extend_one<C: Extend<V>, V>(mut collection: C, value: V) -> C {
+    collection.extend(Some(value));
+    collection
+}
+
+fn ignore<V>(_: (), _: V) {}
+
+pub struct Fold<S, A, F, V> {
+    sequence: S,
+    init: A,
+    func: F,
+    _phantom: PhantomData<V>,
+}
+
+impl<S, A, F, V> Fold<S, A, F, V> {
+    pub fn new(sequence: S, init: A, func: F) -> Self {
+        Self {
+            sequence,
+            init,
+            func,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+struct State<A, F, R> {
+    inner: Mutex<Inner<A, F, R>>,
+    /// Stops the sequence once an item completes with an error.
+    stop_source: StopSource,
+}
+
+struct Inner<A, F, R> {
+    acc: Option<A>,
+    func: F,
+    error: Option<ForwardError<R>>,
+}
+
+pub struct FoldReceiver<A, F, V, R> {
+    state: Arc<State<A, F, R>>,
+    receiver: R,
+    _phantom: PhantomData<V>,
+}
+
+impl<A, F, V, R> FoldReceiver<A, F, V, R> {
+    /// Completes with the error of an item if there was one.
+    fn take_error(&self) -> Option<ForwardError<R>> {
+        self.state.inner.lock().unwrap().error.take()
+    }
+}
+
+impl<S, A, F, V, R> SetNext<S> for FoldReceiver<A, F, V, R>
+where
+    S: Sender<FoldItemReceiver<A, F, V, R>>,
+{
+    type Next = FoldNext<S, A, F, V, R>;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        FoldNext {
+            item,
+            state: self.state.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<A, F, V, R> SetValue for FoldReceiver<A, F, V, R>
+where
+    R: SetValue<Value = A>,
+{
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        if let Some(forward_error) = self.take_error() {
+            return forward_error(self.receiver);
+        }
+        let acc = self.state.inner.lock().unwrap().acc.take().unwrap();
+        self.receiver.set_value(acc);
+    }
+}
+
+impl<A, F, V, R> SetError for FoldReceiver<A, F, V, R>
+where
+    R: SetError,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        match self.take_error() {
+            Some(forward_error) => forward_error(self.receiver),
+            None => self.receiver.set_error(error),
+        }
+    }
+}
+
+impl<A, F, V, R> SetStopped for FoldReceiver<A, F, V, R>
+where
+    R: SetStopped,
+{
+    fn set_stopped(self) {
+        match self.take_error() {
+            Some(forward_error) => forward_error(self.receiver),
+            None => self.receiver.set_stopped(),
+        }
+    }
+}
+
+impl<A, F, V, R> GetStopToken for FoldReceiver<A, F, V, R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+pub struct FoldNext<S, A, F, V, R> {
+    item: S,
+    state: Arc<State<A, F, R>>,
+    _phantom: PhantomData<V>,
+}
+
+impl<S, A, F, V, R> Sender<NextReceiver> for FoldNext<S, A, F, V, R>
+where
+    S: Sender<FoldItemReceiver<A, F, V, R>>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = S::Operation;
+
+    fn connect(self, receiver: NextReceiver) -> Self::Operation {
+        self.item.connect(FoldItemReceiver {
+            state: self.state,
+            receiver,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+pub struct FoldItemReceiver<A, F, V, R> {
+    state: Arc<State<A, F, R>>,
+    receiver: NextReceiver,
+    _phantom: PhantomData<V>,
+}
+
+impl<A, F, V, R> SetValue for FoldItemReceiver<A, F, V, R>
+where
+    F: FnMut(A, V) -> A,
+{
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        {
+            let mut inner = self.state.inner.lock().unwrap();
+            let Inner { acc, func, .. } = &mut *inner;
+            *acc = Some(func(acc.take().unwrap(), value));
+        }
+        self.receiver.set_value(());
+    }
+}
+
+impl<A, F, V, R> SetError for FoldItemReceiver<A, F, V, R>
+where
+    R: SetError,
+    R::Error: Send + 'static,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        {
+            let mut inner = self.state.inner.lock().unwrap();
+            inner.error = inner.error.take().or(Some(forward_error(error)));
+        }
+        self.state.stop_source.request_stop();
+        self.receiver.set_stopped();
+    }
+}
+
+impl<A, F, V, R> SetStopped for FoldItemReceiver<A, F, V, R> {
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<A, F, V, R> GetStopToken for FoldItemReceiver<A, F, V, R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+pub struct FoldOperation<O, A, F, R> {
+    operation: O,
+    state: Arc<State<A, F, R>>,
+    /// The stop token of the receiver.
+    stop_token: StopToken,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<O, A, F, R> OperationState for FoldOperation<O, A, F, R>
+where
+    O: OperationState,
+{
+    fn start(&mut self) {
+        self.stop_callback = Some(link_stop(&self.stop_token, &self.state.stop_source));
+        self.operation.start();
+    }
+}
+
+impl<S, A, F, V, R> Sender<R> for Fold<S, A, F, V>
+where
+    S: SequenceSender<FoldReceiver<A, F, V, R>>,
+    R: SetValue<Value = A> + SetError + GetStopToken,
+{
+    type Value = A;
+    type Error = R::Error;
+
+    type Operation = FoldOperation<S::Operation, A, F, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let stop_token = receiver.get_stop_token();
+        let state = Arc::new(State {
+            inner: Mutex::new(Inner {
+                acc: Some(self.init),
+                func: self.func,
+                error: None,
+            }),
+            stop_source: StopSource::new(),
+        });
+        FoldOperation {
+            operation: self.sequence.subscribe(FoldReceiver {
+                state: state.clone(),
+                receiver,
+                _phantom: PhantomData,
+            }),
+            state,
+            stop_token,
+            stop_callback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then_try;
+    use crate::consumers::sync_wait;
+    use crate::sequence::{interval, iterate, take, transform_each};
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    #[test]
+    fn test_fold() {
+        let sender = fold(iterate(1..=4), 0, |acc, value| acc + value);
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(10)));
+
+        let sender = collect::<Vec<_>, _, _>(iterate(["a", "b"]));
+        assert_eq!(
+            sync_wait::<_, _, Infallible>(sender),
+            Ok(Some(vec!["a", "b"]))
+        );
+
+        let sender = ignore_all_values(iterate(1..=3));
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(())));
+    }
+
+    #[test]
+    fn test_fold_on_context() {
+        let context = SingleThreadContext::new();
+        let ticks = interval(context.get_scheduler(), Duration::from_millis(1));
+        let sender = fold(take(ticks, 3), 0, |count, ()| count + 1);
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_fold_error() {
+        let sequence = transform_each(iterate(1..), |item| {
+            then_try(
+                item,
+                |value| {
+                    if value < 3 {
+                        Ok(value)
+                    } else {
+                        Err(TestError)
+                    }
+                },
+            )
+        });
+        let sender = collect::<Vec<_>, _, _>(sequence);
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+}
@@ -0,0 +1,403 @@
+use super::utils::{forward_error, link_stop, ForwardError};
+use crate::consumers::submit::{submit, SubmitReceiver};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{OperationState, Sender, SequenceSender, StopCallback, StopSource, StopToken};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Calls `func` with the values of `sequence`, running up to
+/// `max_concurrency` items at once, and completes with `()` once the
+/// sequence ends and its items have completed.
+///
+/// An item completing with an error stops the sequence, which then completes
+/// with the error. Like [`fold`](super::fold), the error type can't be
+/// inferred from a sequence whose items never fail.
+///
+/// # Panics
+///
+/// Panics if `max_concurrency` is 0.
+pub fn for_each<S, F, V>(sequence: S, max_concurrency: usize, func: F) -> ForEach<S, F, V>
+where
+    F: FnMut(V),
+{
+    ForEach::new(sequence, max_concurrency, func)
+}
+
+pub struct ForEach<S, F, V> {
+    sequence: S,
+    max_concurrency: usize,
+    func: F,
+    _phantom: PhantomData<V>,
+}
+
+impl<S, F, V> ForEach<S, F, V> {
+    pub fn new(sequence: S, max_concurrency: usize, func: F) -> Self {
+        assert!(max_concurrency > 0, "max_concurrency must be positive");
+        Self {
+            sequence,
+            max_concurrency,
+            func,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+struct State<F, R> {
+    inner: Mutex<Inner<F, R>>,
+    max_concurrency: usize,
+    /// Stops the sequence once an item completes with an error.
+    stop_source: StopSource,
+}
+
+struct Inner<F, R> {
+    func: F,
+    receiver: Option<R>,
+    in_flight: usize,
+    /// Holds the sequence back until an item completes, one receiver per
+    /// item a merged sequence delivers concurrently.
+    waiting: VecDeque<NextReceiver>,
+    /// Set once the sequence completes, to whether it stopped.
+    ended: Option<bool>,
+    error: Option<ForwardError<R>>,
+}
+
+impl<F, R> State<F, R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    /// Completes the receiver once the sequence and all its items have.
+    fn complete(&self, mut inner: MutexGuard<'_, Inner<F, R>>) {
+        let Some(stopped) = inner.ended.filter(|_| inner.in_flight == 0) else {
+            return;
+        };
+        let Some(receiver) = inner.receiver.take() else {
+            return;
+        };
+        let error = inner.error.take();
+        drop(inner);
+        match error {
+            Some(forward_error) => forward_error(receiver),
+            None if stopped => receiver.set_stopped(),
+            None => receiver.set_value(()),
+        }
+    }
+
+    fn end(&self, stopped: bool, error: Option<ForwardError<R>>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.ended = Some(stopped);
+        inner.error = inner.error.take().or(error);
+        self.complete(inner);
+    }
+
+    fn item_done(&self, error: Option<ForwardError<R>>) {
+        if error.is_some() {
+            self.stop_source.request_stop();
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.error = inner.error.take().or(error);
+        inner.in_flight -= 1;
+        let waiting = inner.waiting.pop_front();
+        drop(inner);
+        if let Some(next) = waiting {
+            next.set_value(());
+        }
+        self.complete(self.inner.lock().unwrap());
+    }
+}
+
+pub struct ForEachReceiver<F, V, R> {
+    state: Arc<State<F, R>>,
+    _phantom: PhantomData<V>,
+}
+
+impl<S, F, V, R> SetNext<S> for ForEachReceiver<F, V, R>
+where
+    S: Sender<SubmitReceiver<ForEachItemReceiver<F, V, R>>>,
+{
+    type Next = ForEachNext<S, F, V, R>;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        ForEachNext {
+            item,
+            state: self.state.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, V, R> SetValue for ForEachReceiver<F, V, R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.state.end(false, None);
+    }
+}
+
+impl<F, V, R> SetError for ForEachReceiver<F, V, R>
+where
+    R: SetValue<Value = ()> + SetError + SetStopped,
+    R::Error: Send + 'static,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.state.end(false, Some(forward_error(error)));
+    }
+}
+
+impl<F, V, R> SetStopped for ForEachReceiver<F, V, R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    fn set_stopped(self) {
+        self.state.end(true, None);
+    }
+}
+
+impl<F, V, R> GetStopToken for ForEachReceiver<F, V, R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+/// Starts an item on the heap, then asks for the next one unless
+/// `max_concurrency` items are running.
+pub struct ForEachNext<S, F, V, R> {
+    item: S,
+    state: Arc<State<F, R>>,
+    _phantom: PhantomData<V>,
+}
+
+impl<S, F, V, R> Sender<NextReceiver> for ForEachNext<S, F, V, R>
+where
+    S: Sender<SubmitReceiver<ForEachItemReceiver<F, V, R>>>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = ForEachNextOperation<S, F, V, R>;
+
+    fn connect(self, receiver: NextReceiver) -> Self::Operation {
+        ForEachNextOperation {
+            item: Some(self.item),
+            state: self.state,
+            receiver: Some(receiver),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+pub struct ForEachNextOperation<S, F, V, R> {
+    item: Option<S>,
+    state: Arc<State<F, R>>,
+    receiver: Option<NextReceiver>,
+    _phantom: PhantomData<V>,
+}
+
+impl<S, F, V, R> OperationState for ForEachNextOperation<S, F, V, R>
+where
+    S: Sender<SubmitReceiver<ForEachItemReceiver<F, V, R>>>,
+{
+    fn start(&mut self) {
+        {
+            let mut inner = self.state.inner.lock().unwrap();
+            inner.in_flight += 1;
+            inner.waiting.extend(self.receiver.take());
+        }
+        let receiver = ForEachItemReceiver {
+            state: self.state.clone(),
+            _phantom: PhantomData,
+        };
+        submit(self.item.take().unwrap(), receiver);
+
+        let mut inner = self.state.inner.lock().unwrap();
+        if inner.in_flight < self.state.max_concurrency {
+            let waiting = inner.waiting.pop_front();
+            drop(inner);
+            if let Some(next) = waiting {
+                next.set_value(());
+            }
+        }
+    }
+}
+
+pub struct ForEachItemReceiver<F, V, R> {
+    state: Arc<State<F, R>>,
+    _phantom: PhantomData<V>,
+}
+
+impl<F, V, R> SetValue for ForEachItemReceiver<F, V, R>
+where
+    F: FnMut(V),
+    R: SetValue<Value = ()> + SetStopped,
+{
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        (self.state.inner.lock().unwrap().func)(value);
+        self.state.item_done(None);
+    }
+}
+
+impl<F, V, R> SetError for ForEachItemReceiver<F, V, R>
+where
+    R: SetValue<Value = ()> + SetError + SetStopped,
+    R::Error: Send + 'static,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.state.item_done(Some(forward_error(error)));
+    }
+}
+
+impl<F, V, R> SetStopped for ForEachItemReceiver<F, V, R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    fn set_stopped(self) {
+        self.state.item_done(None);
+    }
+}
+
+impl<F, V, R> GetStopToken for ForEachItemReceiver<F, V, R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+pub struct ForEachOperation<O, F, R> {
+    operation: O,
+    state: Arc<State<F, R>>,
+    /// The stop token of the receiver.
+    stop_token: StopToken,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<O, F, R> OperationState for ForEachOperation<O, F, R>
+where
+    O: OperationState,
+{
+    fn start(&mut self) {
+        self.stop_callback = Some(link_stop(&self.stop_token, &self.state.stop_source));
+        self.operation.start();
+    }
+}
+
+impl<S, F, V, R> Sender<R> for ForEach<S, F, V>
+where
+    S: SequenceSender<ForEachReceiver<F, V, R>>,
+    R: SetValue<Value = ()> + SetError + SetStopped + GetStopToken,
+{
+    type Value = ();
+    type Error = R::Error;
+
+    type Operation = ForEachOperation<S::Operation, F, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        let stop_token = receiver.get_stop_token();
+        let state = Arc::new(State {
+            inner: Mutex::new(Inner {
+                func: self.func,
+                receiver: Some(receiver),
+                in_flight: 0,
+                waiting: VecDeque::new(),
+                ended: None,
+                error: None,
+            }),
+            max_concurrency: self.max_concurrency,
+            stop_source: StopSource::new(),
+        });
+        ForEachOperation {
+            operation: self.sequence.subscribe(ForEachReceiver {
+                state: state.clone(),
+                _phantom: PhantomData,
+            }),
+            state,
+            stop_token,
+            stop_callback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then_try;
+    use crate::consumers::sync_wait;
+    use crate::sequence::{interval, iterate, merge, take, transform_each};
+    use exec_core::TimedScheduler;
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_for_each() {
+        let mut values = Vec::new();
+        let sender = for_each(iterate(1..=3), 1, |value| values.push(value));
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(())));
+        assert_eq!(values, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_for_each_concurrency() {
+        let context = SingleThreadContext::new();
+        let scheduler = context.get_scheduler();
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let sequence = transform_each(iterate(0..8), {
+            let running = running.clone();
+            let max_running = max_running.clone();
+            move |_| {
+                let running_now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(running_now, Ordering::SeqCst);
+                scheduler.clone().schedule_after(Duration::from_millis(5))
+            }
+        });
+        let sender = for_each(sequence, 3, |()| {
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(())));
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_for_each_error() {
+        let sequence = transform_each(iterate(1..), |item| {
+            then_try(
+                item,
+                |value| if value < 3 { Ok(value) } else { Err(TestError) },
+            )
+        });
+        let sender = for_each(sequence, 2, |_| {});
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+
+    #[test]
+    fn test_for_each_merged_items() {
+        // Both merged sequences deliver items while the one running item
+        // holds the others back.
+        let context = SingleThreadContext::new();
+        let ticks = || {
+            take(
+                interval(context.get_scheduler(), Duration::from_millis(1)),
+                10,
+            )
+        };
+        let calls = AtomicUsize::new(0);
+        let sender = for_each(merge([ticks(), ticks()], 2), 1, |()| {
+            calls.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq!(sync_wait::<_, _, Infallible>(sender), Ok(Some(())));
+        assert_eq!(calls.load(Ordering::SeqCst), 20);
+    }
+}
@@ -0,0 +1,222 @@
+use super::utils::start_next;
+use crate::consumers::submit::{submit, SubmitReceiver};
+use crate::factories::{from_future_on, just, Just, PollReceiver};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::SetNext;
+use exec_core::{OperationState, Scheduler, SequenceSender, StopToken};
+use futures::future::Map;
+use futures::stream::StreamFuture;
+use futures::{FutureExt, Stream, StreamExt};
+use std::convert::Infallible;
+use std::error::Error;
+use std::pin::Pin;
+
+/// Turns a stream into a sequence.
+///
+/// The stream is polled on `scheduler` with [`from_future_on`], one item at a
+/// time: the next item is polled for once the previous one has been consumed.
+/// `Ok` items are passed on as [`just`] senders, an `Err` item completes the
+/// sequence with the error and the end of the stream with `()`. Stop
+/// requested through the receiver's stop token drops the stream and
+/// completes with stopped.
+pub fn from_stream<Sch, St>(scheduler: Sch, stream: St) -> FromStream<Sch, St> {
+    FromStream::new(scheduler, stream)
+}
+
+pub struct FromStream<Sch, St> {
+    scheduler: Sch,
+    stream: St,
+}
+
+impl<Sch, St> FromStream<Sch, St> {
+    pub fn new(scheduler: Sch, stream: St) -> Self {
+        Self { scheduler, stream }
+    }
+}
+
+type Polled<St> = (Option<<St as Stream>::Item>, Pin<Box<St>>);
+
+/// Polls the stream for its next item, handing the stream back with it.
+type NextItem<St> =
+    Map<StreamFuture<Pin<Box<St>>>, fn(Polled<St>) -> Result<Polled<St>, Infallible>>;
+
+type PollNext<Sch, St, R> =
+    PollReceiver<Sch, NextItem<St>, SubmitReceiver<NextItemReceiver<Sch, St, R>>>;
+
+/// The receiver of the sequence, passed on from one item to the next.
+struct Driver<Sch, St, R> {
+    scheduler: Sch,
+    receiver: R,
+    /// Polls the stream for the next item on `scheduler`. Stored as a
+    /// function pointer so that the receivers below don't need to repeat the
+    /// scheduler bounds, which would make them recursive.
+    poll_next: fn(Self, Pin<Box<St>>),
+}
+
+fn poll_next<Sch, St, R>(driver: Driver<Sch, St, R>, stream: Pin<Box<St>>)
+where
+    Sch: Scheduler<SubmitReceiver<PollNext<Sch, St, R>>> + 'static,
+    St: Stream + Send + 'static,
+    St::Item: Send,
+    NextItemReceiver<Sch, St, R>: SetValue<Value = Polled<St>> + SetStopped + GetStopToken,
+    R: Send + 'static,
+{
+    let item: NextItem<St> = stream.into_future().map(Ok);
+    let sender = from_future_on(driver.scheduler.clone(), item);
+    submit(sender, NextItemReceiver { driver });
+}
+
+/// Receives the next item of the stream, or its end.
+pub struct NextItemReceiver<Sch, St, R> {
+    driver: Driver<Sch, St, R>,
+}
+
+impl<Sch, St, T, E, R> SetValue for NextItemReceiver<Sch, St, R>
+where
+    St: Stream<Item = Result<T, E>>,
+    R: SetNext<Just<T>> + SetValue<Value = ()> + SetError<Error = E> + SetStopped + GetStopToken,
+    E: Error,
+{
+    type Value = Polled<St>;
+
+    fn set_value(self, (item, stream): Self::Value) {
+        let Driver {
+            scheduler,
+            mut receiver,
+            poll_next,
+        } = self.driver;
+        match item {
+            Some(Ok(value)) => {
+                let next = receiver.set_next(just(value));
+                let stop_token = receiver.get_stop_token();
+                start_next(next, stop_token, move |more| {
+                    if more {
+                        let driver = Driver {
+                            scheduler,
+                            receiver,
+                            poll_next,
+                        };
+                        poll_next(driver, stream);
+                    } else {
+                        drop(stream);
+                        receiver.set_stopped();
+                    }
+                });
+            }
+            Some(Err(error)) => {
+                drop(stream);
+                receiver.set_error(error);
+            }
+            None => {
+                drop(stream);
+                receiver.set_value(());
+            }
+        }
+    }
+}
+
+impl<Sch, St, R> SetError for NextItemReceiver<Sch, St, R> {
+    type Error = Infallible;
+
+    fn set_error(self, error: Self::Error) {
+        match error {}
+    }
+}
+
+impl<Sch, St, R: SetStopped> SetStopped for NextItemReceiver<Sch, St, R> {
+    fn set_stopped(self) {
+        self.driver.receiver.set_stopped();
+    }
+}
+
+impl<Sch, St, R: GetStopToken> GetStopToken for NextItemReceiver<Sch, St, R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.driver.receiver.get_stop_token()
+    }
+}
+
+pub struct FromStreamOperation<Sch, St, R> {
+    start: Option<(Driver<Sch, St, R>, St)>,
+}
+
+impl<Sch, St, R> OperationState for FromStreamOperation<Sch, St, R> {
+    fn start(&mut self) {
+        if let Some((driver, stream)) = self.start.take() {
+            (driver.poll_next)(driver, Box::pin(stream));
+        }
+    }
+}
+
+impl<Sch, St, T, E, R> SequenceSender<R> for FromStream<Sch, St>
+where
+    Sch: Scheduler<SubmitReceiver<PollNext<Sch, St, R>>> + 'static,
+    St: Stream<Item = Result<T, E>> + Send + 'static,
+    T: Send,
+    E: Error + Send,
+    R: SetNext<Just<T>> + SetValue<Value = ()> + SetError<Error = E> + SetStopped,
+    R: GetStopToken + Send + 'static,
+{
+    type Error = E;
+
+    type Operation = FromStreamOperation<Sch, St, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        let driver = Driver {
+            scheduler: self.scheduler,
+            receiver,
+            poll_next: poll_next::<Sch, St, R>,
+        };
+        FromStreamOperation {
+            start: Some((driver, self.stream)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumers::{sync_wait, sync_wait_for, SyncWaitError};
+    use crate::sequence::collect;
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use futures::channel::mpsc;
+    use futures::stream;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_from_stream() {
+        let context = SingleThreadContext::new();
+        let items = stream::iter([Ok::<_, TestError>(1), Ok(2), Ok(3)]);
+        let sender = collect::<Vec<_>, _, _>(from_stream(context.get_scheduler(), items));
+        assert_eq!(sync_wait(sender), Ok(Some(vec![1, 2, 3])));
+
+        let items = stream::iter([Ok(1), Err(TestError), Ok(3)]);
+        let sender = collect::<Vec<_>, _, _>(from_stream(context.get_scheduler(), items));
+        assert_eq!(sync_wait(sender), Err(TestError));
+    }
+
+    #[test]
+    fn test_from_stream_wake_from_other_thread() {
+        let context = SingleThreadContext::new();
+        let (tx, rx) = mpsc::unbounded();
+        let sender = collect::<Vec<_>, _, _>(from_stream(context.get_scheduler(), rx));
+
+        let worker = thread::spawn(move || {
+            for value in 0..3 {
+                tx.unbounded_send(Ok::<_, TestError>(value)).unwrap();
+            }
+        });
+        assert_eq!(sync_wait(sender), Ok(Some(vec![0, 1, 2])));
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn test_from_stream_stopped() {
+        let context = SingleThreadContext::new();
+        let items = stream::pending::<Result<(), TestError>>();
+        let sender = collect::<Vec<_>, _, _>(from_stream(context.get_scheduler(), items));
+        let result = sync_wait_for(sender, Duration::from_millis(10));
+        assert_eq!(result, Err(SyncWaitError::TimedOut));
+    }
+}
@@ -0,0 +1,171 @@
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{OperationState, Sender};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Produces the item senders of a sequence, one at a time.
+pub trait Generator {
+    type Item;
+
+    fn next(&mut self) -> Option<Self::Item>;
+}
+
+impl<F, S> Generator for F
+where
+    F: FnMut() -> Option<S>,
+{
+    type Item = S;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self()
+    }
+}
+
+/// The next operation is running.
+const RUNNING: u8 = 0;
+/// The next operation will complete later, from wherever it runs.
+const PENDING: u8 = 1;
+/// The next operation completed while starting, more items are wanted.
+const MORE: u8 = 2;
+/// The next operation completed while starting, no more items are wanted.
+const DONE: u8 = 3;
+
+/// Operation of a sequence pulling its items from a [`Generator`], the
+/// building block of the sequence factories.
+///
+/// An item is pulled once the previous one has been consumed. Items consumed
+/// synchronously are looped over rather than recursed into, so the stack
+/// doesn't grow with the length of the sequence.
+pub struct GenerateOperation<G, R>
+where
+    G: Generator,
+    R: SetNext<G::Item>,
+{
+    generator: G,
+    receiver: Option<R>,
+    next: Option<<R::Next as Sender<NextReceiver>>::Operation>,
+    state: AtomicU8,
+}
+
+impl<G, R> GenerateOperation<G, R>
+where
+    G: Generator,
+    R: SetNext<G::Item> + SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    pub fn new(generator: G, receiver: R) -> Self {
+        Self {
+            generator,
+            receiver: Some(receiver),
+            next: None,
+            state: AtomicU8::new(PENDING),
+        }
+    }
+
+    fn run(&mut self) {
+        loop {
+            let op = NonNull::from(&mut *self).cast();
+            let receiver = self.receiver.as_mut().unwrap();
+            let stop_token = receiver.get_stop_token();
+            if stop_token.stop_requested() {
+                return self.receiver.take().unwrap().set_stopped();
+            }
+            let Some(item) = self.generator.next() else {
+                return self.receiver.take().unwrap().set_value(());
+            };
+
+            let next = receiver.set_next(item);
+            let receiver = unsafe { NextReceiver::new(op, Self::complete, stop_token) };
+            // Replacing the previous next operation, which has completed.
+            let next = self.next.insert(next.connect(receiver));
+            self.state.store(RUNNING, Ordering::Relaxed);
+            next.start();
+
+            match self
+                .state
+                .compare_exchange(RUNNING, PENDING, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return,
+                Err(MORE) => continue,
+                Err(_) => return self.receiver.take().unwrap().set_stopped(),
+            }
+        }
+    }
+
+    unsafe fn complete(op: NonNull<()>, more: bool) {
+        let this = &mut *op.cast::<Self>().as_ptr();
+        let state = if more { MORE } else { DONE };
+        if this
+            .state
+            .compare_exchange(RUNNING, state, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            // Completed while starting, `run` carries on.
+            return;
+        }
+
+        if more {
+            this.run();
+        } else {
+            this.receiver.take().unwrap().set_stopped();
+        }
+    }
+}
+
+impl<G, R> OperationState for GenerateOperation<G, R>
+where
+    G: Generator,
+    R: SetNext<G::Item> + SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn start(&mut self) {
+        self.run();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then;
+    use crate::factories::just;
+    use exec_core::Scheduler;
+    use exec_executor::SingleThreadContext;
+    use exec_test::receivers::{CollectReceiver, Collected};
+
+    #[test]
+    fn test_generate() {
+        let mut count = 0;
+        let generator = move || {
+            count += 1;
+            (count <= 3).then(|| just(count))
+        };
+        let (receiver, collected) = CollectReceiver::new();
+        GenerateOperation::new(generator, receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![1, 2, 3]));
+
+        // Synchronous items don't grow the stack.
+        let generator = || Some(just(()));
+        let (receiver, collected) = CollectReceiver::with_limit(100_000);
+        GenerateOperation::new(generator, receiver).start();
+        let Collected::Stopped(items) = collected.recv().unwrap() else {
+            panic!("the sequence didn't stop");
+        };
+        assert_eq!(items.len(), 100_000);
+    }
+
+    #[test]
+    fn test_generate_on_context() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+
+        let mut count = 0;
+        let generator = move || {
+            count += 1;
+            let count = count;
+            (count <= 3).then(|| then(scheduler.schedule(), move |_| count))
+        };
+        let (receiver, collected) = CollectReceiver::new();
+        let mut op = GenerateOperation::new(generator, receiver);
+        op.start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![1, 2, 3]));
+    }
+}
@@ -0,0 +1,125 @@
+use super::{GenerateOperation, Generator};
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::sequence::SetNext;
+use exec_core::{Sender, SequenceSender, TimedScheduler};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// An endless sequence of ticks, one per `period` on `scheduler`, it only
+/// ends when stopped.
+///
+/// Ticks are due at fixed points in time from the first one on, a tick that
+/// is consumed late doesn't delay the following ones.
+pub fn interval<S>(scheduler: S, period: Duration) -> Interval<S> {
+    Interval::new(scheduler, period)
+}
+
+pub struct Interval<S> {
+    scheduler: S,
+    period: Duration,
+}
+
+impl<S> Interval<S> {
+    pub fn new(scheduler: S, period: Duration) -> Self {
+        Self { scheduler, period }
+    }
+}
+
+pub struct Ticks<S> {
+    scheduler: S,
+    period: Duration,
+    count: u32,
+    start: Arc<OnceLock<Instant>>,
+}
+
+impl<S: Clone> Generator for Ticks<S> {
+    type Item = Tick<S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.count += 1;
+        Some(Tick {
+            scheduler: self.scheduler.clone(),
+            offset: self.period * self.count,
+            start: self.start.clone(),
+        })
+    }
+}
+
+/// Completes with `()` once the tick is due.
+pub struct Tick<S> {
+    scheduler: S,
+    offset: Duration,
+    /// When the sequence started, read from the scheduler's clock when the
+    /// first tick is connected.
+    start: Arc<OnceLock<Instant>>,
+}
+
+impl<S, R> Sender<R> for Tick<S>
+where
+    S: TimedScheduler<R>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = <S::TimedSender as Sender<R>>::Operation;
+
+    fn connect(mut self, receiver: R) -> Self::Operation {
+        let start = *self.start.get_or_init(|| self.scheduler.now());
+        self.scheduler
+            .schedule_at(start + self.offset)
+            .connect(receiver)
+    }
+}
+
+impl<S, R> SequenceSender<R> for Interval<S>
+where
+    S: Clone,
+    R: SetNext<Tick<S>> + SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Error = ();
+
+    type Operation = GenerateOperation<Ticks<S>, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        let ticks = Ticks {
+            scheduler: self.scheduler,
+            period: self.period,
+            count: 0,
+            start: Arc::new(OnceLock::new()),
+        };
+        GenerateOperation::new(ticks, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exec_core::OperationState;
+    use exec_executor::SingleThreadContext;
+    use exec_test::receivers::{CollectReceiver, Collected};
+
+    #[test]
+    fn test_interval() {
+        let context = SingleThreadContext::new();
+        let period = Duration::from_millis(5);
+
+        let start = Instant::now();
+        let (receiver, collected) = CollectReceiver::with_limit(3);
+        let mut op = interval(context.get_scheduler(), period).subscribe(receiver);
+        op.start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![(); 3]));
+        assert!(start.elapsed() >= period * 3);
+    }
+
+    #[test]
+    fn test_interval_stops_pending_tick() {
+        let context = SingleThreadContext::new();
+        let (receiver, collected) = CollectReceiver::new();
+        let stop_source = receiver.stop_source();
+        let mut op = interval(context.get_scheduler(), Duration::from_secs(60)).subscribe(receiver);
+        op.start();
+
+        stop_source.request_stop();
+        assert_eq!(collected.recv().unwrap(), Collected::<()>::Stopped(vec![]));
+    }
+}
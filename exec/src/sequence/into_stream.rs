@@ -0,0 +1,352 @@
+use crate::consumers::Orphan;
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{OperationState, Sender, SequenceSender, StopSource, StopToken};
+use futures::Stream;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Turns a sequence into a stream of the results of its items.
+///
+/// The sequence is subscribed to on the first poll. An item is only asked
+/// for once the previous one has been polled out of the stream. An item
+/// completing with an error is yielded as `Err` and stops the sequence, the
+/// error of the sequence itself is yielded as its last item. Dropping the
+/// stream requests stop, the operation is released once the sequence has
+/// completed, on the thread completing it, hence it must be `Send`.
+pub fn into_stream<S, V, E>(sequence: S) -> SequenceStream<S, V, E>
+where
+    S: SequenceSender<IntoStreamReceiver<V, E>>,
+    S::Operation: Send,
+{
+    SequenceStream::new(sequence)
+}
+
+struct Shared<V, E> {
+    inner: Mutex<Inner<V, E>>,
+    stop_source: StopSource,
+}
+
+// `orphan` is only released once the sequence has completed, it is an
+// operation a `SequenceStream` only hands over when it is `Send`.
+unsafe impl<V: Send, E: Send> Send for Shared<V, E> {}
+unsafe impl<V: Send, E: Send> Sync for Shared<V, E> {}
+
+struct Inner<V, E> {
+    /// The item to yield next.
+    item: Option<Result<V, E>>,
+    /// Completes the last item passed on, which holds the sequence back
+    /// until the stream is polled again.
+    next: Option<NextReceiver>,
+    /// The sequence has completed, with the error to yield last if any.
+    done: bool,
+    error: Option<E>,
+    waker: Option<Waker>,
+    /// The stream has been dropped.
+    dropped: bool,
+    /// Operation of a stream dropped before the sequence completed.
+    orphan: Option<Orphan>,
+}
+
+impl<V, E> Shared<V, E> {
+    fn push(&self, item: Result<V, E>, next: NextReceiver) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.dropped {
+            drop(inner);
+            return next.set_stopped();
+        }
+
+        let stop = item.is_err();
+        inner.item = Some(item);
+        let waker = inner.waker.take();
+        if stop {
+            drop(inner);
+            self.stop_source.request_stop();
+            next.set_stopped();
+        } else {
+            inner.next = Some(next);
+            drop(inner);
+        }
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn complete(&self, error: Option<E>) {
+        let (waker, orphan) = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.done = true;
+            inner.error = error;
+            (inner.waker.take(), inner.orphan.take())
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        // Nobody polls the stream anymore, release the operation. The
+        // receiver has been moved out of it, so this is the last access.
+        drop(orphan);
+    }
+}
+
+pub struct IntoStreamReceiver<V, E> {
+    shared: Arc<Shared<V, E>>,
+}
+
+impl<S, V, E> SetNext<S> for IntoStreamReceiver<V, E>
+where
+    S: Sender<StreamItemReceiver<V, E>>,
+{
+    type Next = StreamNext<S, V, E>;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        StreamNext {
+            item,
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<V, E> SetValue for IntoStreamReceiver<V, E> {
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.shared.complete(None);
+    }
+}
+
+impl<V, E: Error> SetError for IntoStreamReceiver<V, E> {
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        self.shared.complete(Some(error));
+    }
+}
+
+impl<V, E> SetStopped for IntoStreamReceiver<V, E> {
+    fn set_stopped(self) {
+        self.shared.complete(None);
+    }
+}
+
+impl<V, E> GetStopToken for IntoStreamReceiver<V, E> {
+    fn get_stop_token(&self) -> StopToken {
+        self.shared.stop_source.token()
+    }
+}
+
+pub struct StreamNext<S, V, E> {
+    item: S,
+    shared: Arc<Shared<V, E>>,
+}
+
+impl<S, V, E> Sender<NextReceiver> for StreamNext<S, V, E>
+where
+    S: Sender<StreamItemReceiver<V, E>>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = S::Operation;
+
+    fn connect(self, receiver: NextReceiver) -> Self::Operation {
+        self.item.connect(StreamItemReceiver {
+            shared: self.shared,
+            receiver,
+        })
+    }
+}
+
+pub struct StreamItemReceiver<V, E> {
+    shared: Arc<Shared<V, E>>,
+    receiver: NextReceiver,
+}
+
+impl<V, E> SetValue for StreamItemReceiver<V, E> {
+    type Value = V;
+
+    fn set_value(self, value: Self::Value) {
+        self.shared.push(Ok(value), self.receiver);
+    }
+}
+
+impl<V, E: Error> SetError for StreamItemReceiver<V, E> {
+    type Error = E;
+
+    fn set_error(self, error: Self::Error) {
+        self.shared.push(Err(error), self.receiver);
+    }
+}
+
+impl<V, E> SetStopped for StreamItemReceiver<V, E> {
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<V, E> GetStopToken for StreamItemReceiver<V, E> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+/// A stream driving a sequence, see [`into_stream`].
+pub struct SequenceStream<S, V, E>
+where
+    S: SequenceSender<IntoStreamReceiver<V, E>>,
+    S::Operation: Send,
+{
+    shared: Arc<Shared<V, E>>,
+    sequence: Option<S>,
+    operation: Option<Box<S::Operation>>,
+    _phantom: PhantomData<fn() -> (V, E)>,
+}
+
+impl<S, V, E> SequenceStream<S, V, E>
+where
+    S: SequenceSender<IntoStreamReceiver<V, E>>,
+    S::Operation: Send,
+{
+    pub fn new(sequence: S) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                inner: Mutex::new(Inner {
+                    item: None,
+                    next: None,
+                    done: false,
+                    error: None,
+                    waker: None,
+                    dropped: false,
+                    orphan: None,
+                }),
+                stop_source: StopSource::new(),
+            }),
+            sequence: Some(sequence),
+            operation: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S, V, E> Stream for SequenceStream<S, V, E>
+where
+    S: SequenceSender<IntoStreamReceiver<V, E>>,
+    S::Operation: Send,
+{
+    type Item = Result<V, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Nothing is structurally pinned, the operation lives in its own box.
+        let me = unsafe { self.get_unchecked_mut() };
+
+        if let Some(sequence) = me.sequence.take() {
+            let receiver = IntoStreamReceiver {
+                shared: me.shared.clone(),
+            };
+            me.operation
+                .insert(Box::new(sequence.subscribe(receiver)))
+                .start();
+        }
+
+        let mut inner = me.shared.inner.lock().unwrap();
+        if inner.item.is_none() {
+            if let Some(next) = inner.next.take() {
+                // The last item has been yielded, ask for the next one.
+                drop(inner);
+                next.set_value(());
+                inner = me.shared.inner.lock().unwrap();
+            }
+        }
+
+        if let Some(item) = inner.item.take() {
+            return Poll::Ready(Some(item));
+        }
+        if inner.done {
+            return Poll::Ready(inner.error.take().map(Err));
+        }
+        // Registered under the lock, an item pushed from now on wakes it.
+        inner.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<S, V, E> Drop for SequenceStream<S, V, E>
+where
+    S: SequenceSender<IntoStreamReceiver<V, E>>,
+    S::Operation: Send,
+{
+    fn drop(&mut self) {
+        let Some(operation) = self.operation.take() else {
+            return;
+        };
+
+        let mut inner = self.shared.inner.lock().unwrap();
+        inner.dropped = true;
+        if inner.done {
+            drop(inner);
+            drop(operation);
+            return;
+        }
+        inner.orphan = Some(Orphan::new(operation));
+        let next = inner.next.take();
+        drop(inner);
+
+        self.shared.stop_source.request_stop();
+        if let Some(next) = next {
+            next.set_stopped();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptors::then_try;
+    use crate::sequence::{interval, iterate, transform_each};
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use futures::executor::block_on;
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    #[test]
+    fn test_into_stream() {
+        let stream = into_stream::<_, _, ()>(iterate(1..=3));
+        assert_eq!(block_on(stream.collect::<Vec<_>>()), [Ok(1), Ok(2), Ok(3)]);
+
+        // Dropping the stream stops the sequence.
+        let stream = into_stream::<_, _, ()>(iterate(1..));
+        assert_eq!(block_on(stream.take(2).collect::<Vec<_>>()), [Ok(1), Ok(2)]);
+    }
+
+    #[test]
+    fn test_into_stream_error() {
+        let sequence = transform_each(iterate(1..), |item| {
+            then_try(
+                item,
+                |value| if value < 2 { Ok(value) } else { Err(TestError) },
+            )
+        });
+        let stream = into_stream(sequence);
+        assert_eq!(
+            block_on(stream.collect::<Vec<_>>()),
+            [Ok(1), Err(TestError)]
+        );
+    }
+
+    #[test]
+    fn test_into_stream_on_context() {
+        let context = SingleThreadContext::new();
+        let ticks = interval(context.get_scheduler(), Duration::from_millis(1));
+        let mut stream = into_stream::<_, _, ()>(ticks);
+        block_on(async {
+            assert_eq!(stream.next().await, Some(Ok(())));
+            assert_eq!(stream.next().await, Some(Ok(())));
+        });
+        // Dropped while the next tick is pending.
+        drop(stream);
+    }
+}
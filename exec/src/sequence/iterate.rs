@@ -0,0 +1,64 @@
+use super::{GenerateOperation, Generator};
+use crate::factories::{just, Just};
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::sequence::SetNext;
+use exec_core::SequenceSender;
+
+/// A sequence of the items of `iter`, each passed as a [`just`] sender.
+pub fn iterate<I: IntoIterator>(iter: I) -> Iterate<I::IntoIter> {
+    Iterate::new(iter.into_iter())
+}
+
+pub struct Iterate<I> {
+    iter: I,
+}
+
+impl<I> Iterate<I> {
+    pub fn new(iter: I) -> Self {
+        Self { iter }
+    }
+}
+
+pub struct IterateItems<I> {
+    iter: I,
+}
+
+impl<I: Iterator> Generator for IterateItems<I> {
+    type Item = Just<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(just)
+    }
+}
+
+impl<I, R> SequenceSender<R> for Iterate<I>
+where
+    I: Iterator,
+    R: SetNext<Just<I::Item>> + SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Error = ();
+
+    type Operation = GenerateOperation<IterateItems<I>, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        GenerateOperation::new(IterateItems { iter: self.iter }, receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exec_core::OperationState;
+    use exec_test::receivers::{CollectReceiver, Collected};
+
+    #[test]
+    fn test_iterate() {
+        let (receiver, collected) = CollectReceiver::new();
+        iterate(1..=3).subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![1, 2, 3]));
+
+        let (receiver, collected) = CollectReceiver::with_limit(2);
+        iterate(1..).subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![1, 2]));
+    }
+}
@@ -0,0 +1,323 @@
+use super::utils::{complete_sequence, forward_error, link_stop, ForwardError};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::SetNext;
+use exec_core::{OperationState, SequenceSender, StopCallback, StopSource, StopToken};
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+
+/// Interleaves the items of `sequences` as they come, running up to
+/// `max_concurrency` of them at once. The next sequence is subscribed to
+/// once a running one completes.
+///
+/// # Panics
+///
+/// Panics if `max_concurrency` is 0.
+pub fn merge<S>(sequences: impl IntoIterator<Item = S>, max_concurrency: usize) -> Merge<S> {
+    Merge::new(sequences.into_iter().collect(), max_concurrency)
+}
+
+pub struct Merge<S> {
+    sequences: Vec<S>,
+    max_concurrency: usize,
+}
+
+impl<S> Merge<S> {
+    pub fn new(sequences: Vec<S>, max_concurrency: usize) -> Self {
+        assert!(max_concurrency > 0, "max_concurrency must be positive");
+        Self {
+            sequences,
+            max_concurrency,
+        }
+    }
+}
+
+/// Subscribes to the pending sequences, with the merge operation it points
+/// to.
+struct Launch {
+    op: NonNull<()>,
+    launch: unsafe fn(NonNull<()>),
+}
+
+// The merge operation outlives the sequences it subscribed to.
+unsafe impl Send for Launch {}
+
+struct State<R> {
+    inner: Mutex<Inner<R>>,
+    /// Stops every sequence.
+    stop_source: StopSource,
+}
+
+struct Inner<R> {
+    receiver: Option<R>,
+    /// The index of the next sequence to subscribe to.
+    next: usize,
+    count: usize,
+    running: usize,
+    /// How many more sequences can be running.
+    free: usize,
+    /// Whether sequences are being subscribed to, by a single caller at a
+    /// time. That caller is the one to complete the merge, so the operation
+    /// stays alive while it runs.
+    launching: bool,
+    /// Cleared on completion.
+    launch: Option<Launch>,
+    error: Option<ForwardError<R>>,
+}
+
+pub struct MergeReceiver<R> {
+    state: Arc<State<R>>,
+}
+
+impl<R> MergeReceiver<R> {
+    fn finish(self, error: Option<ForwardError<R>>) {
+        if error.is_some() {
+            self.state.stop_source.request_stop();
+        }
+
+        let mut inner = self.state.inner.lock().unwrap();
+        inner.error = inner.error.take().or(error);
+        inner.running -= 1;
+        inner.free += 1;
+        if inner.launching {
+            // The caller subscribing to sequences picks the freed slot up.
+            return;
+        }
+        inner.launching = true;
+        let launch = inner.launch.as_ref().unwrap();
+        let (op, launch) = (launch.op, launch.launch);
+        drop(inner);
+        unsafe { launch(op) }
+    }
+}
+
+impl<S, R> SetNext<S> for MergeReceiver<R>
+where
+    R: SetNext<S>,
+{
+    type Next = R::Next;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        let mut inner = self.state.inner.lock().unwrap();
+        inner.receiver.as_mut().unwrap().set_next(item)
+    }
+}
+
+impl<R> SetValue for MergeReceiver<R> {
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.finish(None);
+    }
+}
+
+impl<R> SetError for MergeReceiver<R>
+where
+    R: SetError,
+    R::Error: Send + 'static,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.finish(Some(forward_error(error)));
+    }
+}
+
+impl<R> SetStopped for MergeReceiver<R> {
+    fn set_stopped(self) {
+        self.finish(None);
+    }
+}
+
+impl<R> GetStopToken for MergeReceiver<R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+pub struct MergeOperation<S, R>
+where
+    S: SequenceSender<MergeReceiver<R>>,
+{
+    sequences: Vec<Option<S>>,
+    /// One slot per sequence, never resized so that the operations don't
+    /// move once started.
+    operations: Vec<Option<S::Operation>>,
+    state: Arc<State<R>>,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<S, R> MergeOperation<S, R>
+where
+    S: SequenceSender<MergeReceiver<R>>,
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    /// Subscribes to sequences while there are free slots, or completes
+    /// the merge once all are done. The caller has set `launching`.
+    unsafe fn launch(op: NonNull<()>) {
+        let this = &mut *op.cast::<Self>().as_ptr();
+        let mut inner = this.state.inner.lock().unwrap();
+        while inner.free > 0 && inner.next < inner.count && !this.state.stop_source.stop_requested()
+        {
+            let index = inner.next;
+            inner.next += 1;
+            inner.free -= 1;
+            inner.running += 1;
+            drop(inner);
+
+            let sequence = this.sequences[index].take().unwrap();
+            let receiver = MergeReceiver {
+                state: this.state.clone(),
+            };
+            this.operations[index]
+                .insert(sequence.subscribe(receiver))
+                .start();
+            inner = this.state.inner.lock().unwrap();
+        }
+        inner.launching = false;
+
+        let pending = inner.next < inner.count && !this.state.stop_source.stop_requested();
+        if inner.running > 0 || pending {
+            return;
+        }
+        let Some(receiver) = inner.receiver.take() else {
+            return;
+        };
+        let error = inner.error.take();
+        inner.launch = None;
+        drop(inner);
+        complete_sequence(receiver, error);
+    }
+}
+
+impl<S, R> OperationState for MergeOperation<S, R>
+where
+    S: SequenceSender<MergeReceiver<R>>,
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn start(&mut self) {
+        let op = NonNull::from(&mut *self).cast();
+        let token = {
+            let mut inner = self.state.inner.lock().unwrap();
+            inner.launch = Some(Launch {
+                op,
+                launch: Self::launch,
+            });
+            inner.launching = true;
+            inner.receiver.as_ref().unwrap().get_stop_token()
+        };
+        self.stop_callback = Some(link_stop(&token, &self.state.stop_source));
+        unsafe { Self::launch(op) }
+    }
+}
+
+impl<S, R> SequenceSender<R> for Merge<S>
+where
+    S: SequenceSender<MergeReceiver<R>>,
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Error = S::Error;
+
+    type Operation = MergeOperation<S, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        let count = self.sequences.len();
+        MergeOperation {
+            sequences: self.sequences.into_iter().map(Some).collect(),
+            operations: (0..count).map(|_| None).collect(),
+            state: Arc::new(State {
+                inner: Mutex::new(Inner {
+                    receiver: Some(receiver),
+                    next: 0,
+                    count,
+                    running: 0,
+                    free: self.max_concurrency,
+                    launching: false,
+                    launch: None,
+                    error: None,
+                }),
+                stop_source: StopSource::new(),
+            }),
+            stop_callback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{interval, iterate, take, transform_each, Iterate};
+    use crate::then;
+    use exec_executor::SingleThreadContext;
+    use exec_test::receivers::{CollectReceiver, Collected};
+    use std::time::Duration;
+
+    #[test]
+    fn test_merge() {
+        // One at a time, the sequences are concatenated.
+        let (receiver, collected) = CollectReceiver::new();
+        merge([iterate(1..=2), iterate(3..=4), iterate(5..=6)], 1)
+            .subscribe(receiver)
+            .start();
+        assert_eq!(
+            collected.recv().unwrap(),
+            Collected::Value(vec![1, 2, 3, 4, 5, 6])
+        );
+
+        let (receiver, collected) = CollectReceiver::new();
+        merge(std::iter::empty::<Iterate<std::vec::IntoIter<i32>>>(), 1)
+            .subscribe(receiver)
+            .start();
+        assert_eq!(
+            collected.recv().unwrap(),
+            Collected::Value(Vec::<i32>::new())
+        );
+    }
+
+    #[test]
+    fn test_merge_on_context() {
+        let context = SingleThreadContext::new();
+        let ticks = |value: u32| {
+            let ticks = interval(context.get_scheduler(), Duration::from_millis(1));
+            let ticks = transform_each(ticks, move |tick| then(tick, move |()| value));
+            take(ticks, 3)
+        };
+
+        let (receiver, collected) = CollectReceiver::new();
+        let mut op = merge([ticks(1), ticks(2)], 2).subscribe(receiver);
+        op.start();
+        let Collected::Value(mut values) = collected.recv().unwrap() else {
+            panic!("the sequence didn't complete with a value");
+        };
+        values.sort();
+        assert_eq!(values, [1, 1, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_merge_on_two_contexts() {
+        let contexts = [SingleThreadContext::new(), SingleThreadContext::new()];
+        for max_concurrency in [1, 2] {
+            for _ in 0..500 {
+                let sequences = contexts.iter().map(|context| {
+                    let ticks = interval(context.get_scheduler(), Duration::ZERO);
+                    take(ticks, 1)
+                });
+                let (receiver, collected) = CollectReceiver::new();
+                let mut op = merge(sequences, max_concurrency).subscribe(receiver);
+                op.start();
+                let Collected::Value(values) = collected.recv().unwrap() else {
+                    panic!("the sequence didn't complete with a value");
+                };
+                assert_eq!(values.len(), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_stopped() {
+        let (receiver, collected) = CollectReceiver::with_limit(3);
+        merge([iterate(1..), iterate(1..)], 2)
+            .subscribe(receiver)
+            .start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![1, 2, 3]));
+    }
+}
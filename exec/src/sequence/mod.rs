@@ -0,0 +1,34 @@
+//! Sequence senders, see [`exec_core::sequence`].
+
+mod empty_sequence;
+mod filter_each;
+mod fold;
+mod for_each;
+mod from_stream;
+mod generate;
+mod interval;
+mod into_stream;
+mod iterate;
+mod merge;
+mod repeat;
+mod take;
+mod take_until;
+mod transform_each;
+mod utils;
+mod zip;
+
+pub use empty_sequence::{empty_sequence, EmptySequence};
+pub use filter_each::{filter_each, FilterEach};
+pub use fold::{collect, fold, ignore_all_values, Collect, Fold, IgnoreAllValues};
+pub use for_each::{for_each, ForEach};
+pub use from_stream::{from_stream, FromStream, FromStreamOperation, NextItemReceiver};
+pub use generate::{GenerateOperation, Generator};
+pub use interval::{interval, Interval, Tick};
+pub use into_stream::{into_stream, IntoStreamReceiver, SequenceStream};
+pub use iterate::{iterate, Iterate};
+pub use merge::{merge, Merge};
+pub use repeat::{repeat, Repeat};
+pub use take::{take, Take};
+pub use take_until::{take_until, TakeUntil};
+pub use transform_each::{transform_each, TransformEach};
+pub use zip::{zip, Zip};
@@ -0,0 +1,74 @@
+use super::{GenerateOperation, Generator};
+use exec_core::receiver::{GetStopToken, SetStopped, SetValue};
+use exec_core::sequence::SetNext;
+use exec_core::SequenceSender;
+
+/// An endless sequence of the senders returned by `factory`, it only ends
+/// when stopped.
+pub fn repeat<F>(factory: F) -> Repeat<F> {
+    Repeat::new(factory)
+}
+
+pub struct Repeat<F> {
+    factory: F,
+}
+
+impl<F> Repeat<F> {
+    pub fn new(factory: F) -> Self {
+        Self { factory }
+    }
+}
+
+pub struct RepeatItems<F> {
+    factory: F,
+}
+
+impl<F, S> Generator for RepeatItems<F>
+where
+    F: FnMut() -> S,
+{
+    type Item = S;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some((self.factory)())
+    }
+}
+
+impl<F, S, R> SequenceSender<R> for Repeat<F>
+where
+    F: FnMut() -> S,
+    R: SetNext<S> + SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Error = ();
+
+    type Operation = GenerateOperation<RepeatItems<F>, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        GenerateOperation::new(
+            RepeatItems {
+                factory: self.factory,
+            },
+            receiver,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::factories::just;
+    use exec_core::OperationState;
+    use exec_test::receivers::{CollectReceiver, Collected};
+
+    #[test]
+    fn test_repeat() {
+        let mut count = 0;
+        let sequence = repeat(move || {
+            count += 1;
+            just(count)
+        });
+        let (receiver, collected) = CollectReceiver::with_limit(3);
+        sequence.subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![1, 2, 3]));
+    }
+}
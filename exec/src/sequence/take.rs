@@ -0,0 +1,209 @@
+use super::utils::start_next;
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{OperationState, Sender, SequenceSender, StopToken};
+
+/// Passes on the first `count` items of `sequence` and then stops it,
+/// completing with `()`. With a `count` of 0 `sequence` isn't subscribed to.
+pub fn take<S>(sequence: S, count: usize) -> Take<S> {
+    Take::new(sequence, count)
+}
+
+pub struct Take<S> {
+    sequence: S,
+    count: usize,
+}
+
+impl<S> Take<S> {
+    pub fn new(sequence: S, count: usize) -> Self {
+        Self { sequence, count }
+    }
+}
+
+pub struct TakeReceiver<R> {
+    receiver: R,
+    remaining: usize,
+    /// Whether the last item has been passed on, which stops the sequence.
+    taken: bool,
+}
+
+impl<S, R> SetNext<S> for TakeReceiver<R>
+where
+    R: SetNext<S>,
+{
+    type Next = TakeNext<R::Next>;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        if self.remaining == 0 {
+            return TakeNext {
+                next: None,
+                last: true,
+            };
+        }
+        self.remaining -= 1;
+        self.taken = self.remaining == 0;
+        TakeNext {
+            next: Some(self.receiver.set_next(item)),
+            last: self.taken,
+        }
+    }
+}
+
+impl<R: SetValue> SetValue for TakeReceiver<R> {
+    type Value = R::Value;
+
+    fn set_value(self, value: Self::Value) {
+        self.receiver.set_value(value);
+    }
+}
+
+impl<R: SetError> SetError for TakeReceiver<R> {
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_error(error);
+    }
+}
+
+impl<R> SetStopped for TakeReceiver<R>
+where
+    R: SetValue<Value = ()> + SetStopped,
+{
+    fn set_stopped(self) {
+        // Stopped by the last item rather than from downstream.
+        if self.taken {
+            self.receiver.set_value(());
+        } else {
+            self.receiver.set_stopped();
+        }
+    }
+}
+
+impl<R: GetStopToken> GetStopToken for TakeReceiver<R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+/// Consumes an item downstream, the last one then stops the sequence.
+pub struct TakeNext<N> {
+    next: Option<N>,
+    last: bool,
+}
+
+pub enum TakeNextOperation<N: Sender<NextReceiver>> {
+    Next(N::Operation),
+    Last(Option<(N, NextReceiver)>),
+    Done(Option<NextReceiver>),
+}
+
+impl<N> OperationState for TakeNextOperation<N>
+where
+    N: Sender<NextReceiver>,
+{
+    fn start(&mut self) {
+        match self {
+            TakeNextOperation::Next(operation) => operation.start(),
+            TakeNextOperation::Last(last) => {
+                let (next, receiver) = last.take().unwrap();
+                let stop_token = receiver.get_stop_token();
+                start_next(next, stop_token, move |_| receiver.set_stopped());
+            }
+            TakeNextOperation::Done(receiver) => receiver.take().unwrap().set_stopped(),
+        }
+    }
+}
+
+impl<N> Sender<NextReceiver> for TakeNext<N>
+where
+    N: Sender<NextReceiver>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = TakeNextOperation<N>;
+
+    fn connect(self, receiver: NextReceiver) -> Self::Operation {
+        match self.next {
+            Some(next) if self.last => TakeNextOperation::Last(Some((next, receiver))),
+            Some(next) => TakeNextOperation::Next(next.connect(receiver)),
+            None => TakeNextOperation::Done(Some(receiver)),
+        }
+    }
+}
+
+pub enum TakeOperation<O, R> {
+    Subscribed(O),
+    /// Nothing to take, completes with `()` right away.
+    Empty(Option<R>),
+}
+
+impl<O, R> OperationState for TakeOperation<O, R>
+where
+    O: OperationState,
+    R: SetValue<Value = ()>,
+{
+    fn start(&mut self) {
+        match self {
+            TakeOperation::Subscribed(operation) => operation.start(),
+            TakeOperation::Empty(receiver) => receiver.take().unwrap().set_value(()),
+        }
+    }
+}
+
+impl<S, R> SequenceSender<R> for Take<S>
+where
+    S: SequenceSender<TakeReceiver<R>>,
+    R: SetValue<Value = ()>,
+{
+    type Error = S::Error;
+
+    type Operation = TakeOperation<S::Operation, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        if self.count == 0 {
+            return TakeOperation::Empty(Some(receiver));
+        }
+        TakeOperation::Subscribed(self.sequence.subscribe(TakeReceiver {
+            receiver,
+            remaining: self.count,
+            taken: false,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{interval, iterate};
+    use exec_executor::SingleThreadContext;
+    use exec_test::receivers::{CollectReceiver, Collected};
+    use std::time::Duration;
+
+    #[test]
+    fn test_take() {
+        let (receiver, collected) = CollectReceiver::new();
+        take(iterate(1..), 3).subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![1, 2, 3]));
+
+        let (receiver, collected) = CollectReceiver::new();
+        take(iterate(1..=2), 3).subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![1, 2]));
+
+        let (receiver, collected) = CollectReceiver::<i32>::new();
+        take(iterate(1..), 0).subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![]));
+
+        // Not subscribed to, the first tick would only come in a minute.
+        let context = SingleThreadContext::new();
+        let ticks = interval(context.get_scheduler(), Duration::from_secs(60));
+        let (receiver, collected) = CollectReceiver::new();
+        take(ticks, 0).subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![]));
+
+        // Stopped from downstream.
+        let (receiver, collected) = CollectReceiver::with_limit(2);
+        take(iterate(1..), 3).subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![1, 2]));
+    }
+}
@@ -0,0 +1,252 @@
+use super::utils::{complete_sequence, forward_error, link_stop, ForwardError};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::SetNext;
+use exec_core::{OperationState, Sender, SequenceSender, StopCallback, StopSource, StopToken};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// Passes on the items of `sequence` until `trigger` completes, which stops
+/// the sequence and completes with `()`.
+///
+/// The trigger is stopped once the sequence completes on its own.
+pub fn take_until<S, T, V>(sequence: S, trigger: T) -> TakeUntil<S, T, V> {
+    TakeUntil::new(sequence, trigger)
+}
+
+pub struct TakeUntil<S, T, V> {
+    sequence: S,
+    trigger: T,
+    _phantom: PhantomData<V>,
+}
+
+impl<S, T, V> TakeUntil<S, T, V> {
+    pub fn new(sequence: S, trigger: T) -> Self {
+        Self {
+            sequence,
+            trigger,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+struct State<R> {
+    inner: Mutex<Inner<R>>,
+    /// Stops both the sequence and the trigger.
+    stop_source: StopSource,
+}
+
+struct Inner<R> {
+    receiver: Option<R>,
+    /// The sequence and the trigger, until they have completed.
+    remaining: usize,
+    error: Option<ForwardError<R>>,
+}
+
+impl<R> State<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn finish(&self, error: Option<ForwardError<R>>) {
+        self.stop_source.request_stop();
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.error = inner.error.take().or(error);
+        inner.remaining -= 1;
+        if inner.remaining > 0 {
+            return;
+        }
+        let receiver = inner.receiver.take().unwrap();
+        let error = inner.error.take();
+        drop(inner);
+        complete_sequence(receiver, error);
+    }
+}
+
+pub struct TakeUntilReceiver<R> {
+    state: Arc<State<R>>,
+}
+
+impl<S, R> SetNext<S> for TakeUntilReceiver<R>
+where
+    R: SetNext<S>,
+{
+    type Next = R::Next;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        let mut inner = self.state.inner.lock().unwrap();
+        inner.receiver.as_mut().unwrap().set_next(item)
+    }
+}
+
+impl<R> SetValue for TakeUntilReceiver<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.state.finish(None);
+    }
+}
+
+impl<R> SetError for TakeUntilReceiver<R>
+where
+    R: SetValue<Value = ()> + SetError + SetStopped + GetStopToken,
+    R::Error: Send + 'static,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.state.finish(Some(forward_error(error)));
+    }
+}
+
+impl<R> SetStopped for TakeUntilReceiver<R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn set_stopped(self) {
+        self.state.finish(None);
+    }
+}
+
+impl<R> GetStopToken for TakeUntilReceiver<R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+pub struct TriggerReceiver<R, V> {
+    state: Arc<State<R>>,
+    _phantom: PhantomData<V>,
+}
+
+impl<R, V> SetValue for TriggerReceiver<R, V>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Value = V;
+
+    fn set_value(self, _value: Self::Value) {
+        self.state.finish(None);
+    }
+}
+
+impl<R, V> SetStopped for TriggerReceiver<R, V>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn set_stopped(self) {
+        self.state.finish(None);
+    }
+}
+
+impl<R, V> GetStopToken for TriggerReceiver<R, V> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+pub struct TakeUntilOperation<S, T, R> {
+    sequence: S,
+    trigger: T,
+    state: Arc<State<R>>,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<S, T, R> OperationState for TakeUntilOperation<S, T, R>
+where
+    S: OperationState,
+    T: OperationState,
+    R: GetStopToken,
+{
+    fn start(&mut self) {
+        let token = {
+            let inner = self.state.inner.lock().unwrap();
+            inner.receiver.as_ref().unwrap().get_stop_token()
+        };
+        self.stop_callback = Some(link_stop(&token, &self.state.stop_source));
+        self.trigger.start();
+        self.sequence.start();
+    }
+}
+
+impl<S, T, V, R> SequenceSender<R> for TakeUntil<S, T, V>
+where
+    S: SequenceSender<TakeUntilReceiver<R>>,
+    T: Sender<TriggerReceiver<R, V>>,
+    R: GetStopToken,
+{
+    type Error = S::Error;
+
+    type Operation = TakeUntilOperation<S::Operation, T::Operation, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        let state = Arc::new(State {
+            inner: Mutex::new(Inner {
+                receiver: Some(receiver),
+                remaining: 2,
+                error: None,
+            }),
+            stop_source: StopSource::new(),
+        });
+        TakeUntilOperation {
+            sequence: self.sequence.subscribe(TakeUntilReceiver {
+                state: state.clone(),
+            }),
+            trigger: self.trigger.connect(TriggerReceiver {
+                state: state.clone(),
+                _phantom: PhantomData,
+            }),
+            state,
+            stop_callback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{interval, iterate};
+    use exec_core::TimedScheduler;
+    use exec_executor::SingleThreadContext;
+    use exec_test::receivers::{CollectReceiver, Collected};
+    use exec_test::senders::NeverSender;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[test]
+    fn test_take_until() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+
+        let trigger = scheduler.schedule_after(Duration::from_millis(20));
+        let sequence = interval(scheduler.clone(), Duration::from_millis(1));
+        let (receiver, collected) = CollectReceiver::new();
+        let mut op = take_until(sequence, trigger).subscribe(receiver);
+        op.start();
+        let Collected::Value(ticks) = collected.recv().unwrap() else {
+            panic!("the sequence didn't complete with a value");
+        };
+        assert!(!ticks.is_empty());
+    }
+
+    #[test]
+    fn test_take_until_stops_trigger() {
+        let trigger = NeverSender::<()>::new();
+        let stopped = trigger.stopped();
+        let (receiver, collected) = CollectReceiver::new();
+        take_until(iterate(1..=3), trigger)
+            .subscribe(receiver)
+            .start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![1, 2, 3]));
+        assert!(stopped.load(Ordering::SeqCst));
+
+        // Stopped from downstream.
+        let (receiver, collected) = CollectReceiver::with_limit(2);
+        take_until(iterate(1..), NeverSender::<()>::new())
+            .subscribe(receiver)
+            .start();
+        assert_eq!(collected.recv().unwrap(), Collected::Stopped(vec![1, 2]));
+    }
+}
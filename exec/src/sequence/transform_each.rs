@@ -0,0 +1,98 @@
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::SetNext;
+use exec_core::{SequenceSender, StopToken};
+
+/// Replaces each item sender of `sequence` with the sender `func` returns for
+/// it, e.g. `|item| then(item, f)` to map the values.
+pub fn transform_each<S, F>(sequence: S, func: F) -> TransformEach<S, F> {
+    TransformEach::new(sequence, func)
+}
+
+pub struct TransformEach<S, F> {
+    sequence: S,
+    func: F,
+}
+
+impl<S, F> TransformEach<S, F> {
+    pub fn new(sequence: S, func: F) -> Self {
+        Self { sequence, func }
+    }
+}
+
+pub struct TransformEachReceiver<F, R> {
+    func: F,
+    receiver: R,
+}
+
+impl<F, R, I, O> SetNext<I> for TransformEachReceiver<F, R>
+where
+    F: FnMut(I) -> O,
+    R: SetNext<O>,
+{
+    type Next = R::Next;
+
+    fn set_next(&mut self, item: I) -> Self::Next {
+        self.receiver.set_next((self.func)(item))
+    }
+}
+
+impl<F, R: SetValue> SetValue for TransformEachReceiver<F, R> {
+    type Value = R::Value;
+
+    fn set_value(self, value: Self::Value) {
+        self.receiver.set_value(value);
+    }
+}
+
+impl<F, R: SetError> SetError for TransformEachReceiver<F, R> {
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.receiver.set_error(error);
+    }
+}
+
+impl<F, R: SetStopped> SetStopped for TransformEachReceiver<F, R> {
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<F, R: GetStopToken> GetStopToken for TransformEachReceiver<F, R> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+impl<S, F, R> SequenceSender<R> for TransformEach<S, F>
+where
+    S: SequenceSender<TransformEachReceiver<F, R>>,
+{
+    type Error = S::Error;
+
+    type Operation = S::Operation;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        self.sequence.subscribe(TransformEachReceiver {
+            func: self.func,
+            receiver,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::iterate;
+    use crate::then;
+    use exec_core::OperationState;
+    use exec_test::receivers::{CollectReceiver, Collected};
+
+    #[test]
+    fn test_transform_each() {
+        let sequence = transform_each(iterate(1..=3), |item| then(item, |x: i32| x * 2));
+        let (receiver, collected) = CollectReceiver::new();
+        sequence.subscribe(receiver).start();
+        assert_eq!(collected.recv().unwrap(), Collected::Value(vec![2, 4, 6]));
+    }
+}
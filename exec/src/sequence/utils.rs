@@ -0,0 +1,84 @@
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::NextReceiver;
+use exec_core::{OperationState, Sender, StopCallback, StopSource, StopToken};
+use std::ptr::NonNull;
+
+/// Completes the receiver of an adaptor with the error of one of the
+/// sequences it subscribed to, once all of them have completed.
+pub(crate) type ForwardError<R> = Box<dyn FnOnce(R) + Send>;
+
+pub(crate) fn forward_error<R>(error: R::Error) -> ForwardError<R>
+where
+    R: SetError,
+    R::Error: Send + 'static,
+{
+    Box::new(move |receiver: R| receiver.set_error(error))
+}
+
+/// Completes the receiver of an adaptor once all the operations it started
+/// have completed: with the first error, with stopped if stop has been
+/// requested from downstream, with `()` otherwise.
+pub(crate) fn complete_sequence<R>(receiver: R, error: Option<ForwardError<R>>)
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    match error {
+        Some(forward_error) => forward_error(receiver),
+        None if receiver.get_stop_token().stop_requested() => receiver.set_stopped(),
+        None => receiver.set_value(()),
+    }
+}
+
+/// Forwards stop requests from `token` to `source`.
+pub(crate) fn link_stop(token: &StopToken, source: &StopSource) -> StopCallback {
+    let source = source.clone();
+    StopCallback::new(token, move || {
+        source.request_stop();
+    })
+}
+
+/// Completes `receiver`, asking for more items or not.
+pub(crate) fn complete(receiver: NextReceiver, more: bool) {
+    if more {
+        receiver.set_value(());
+    } else {
+        receiver.set_stopped();
+    }
+}
+
+struct NextSlot<O, F> {
+    operation: Option<O>,
+    complete: Option<F>,
+}
+
+/// Starts `next` on the heap, for adaptors passing items on from within the
+/// completion of another operation. `complete` gets whether more items are
+/// wanted once `next` completes.
+pub(crate) fn start_next<N, F>(next: N, stop_token: StopToken, complete: F)
+where
+    N: Sender<NextReceiver>,
+    F: FnOnce(bool),
+{
+    let slot = Box::into_raw(Box::new(NextSlot {
+        operation: None,
+        complete: Some(complete),
+    }));
+    unsafe {
+        let receiver = NextReceiver::new(
+            NonNull::new_unchecked(slot).cast(),
+            complete_slot::<N::Operation, F>,
+            stop_token,
+        );
+        (*slot).operation.insert(next.connect(receiver)).start();
+    }
+}
+
+unsafe fn complete_slot<O, F>(slot: NonNull<()>, more: bool)
+where
+    F: FnOnce(bool),
+{
+    let mut slot = Box::from_raw(slot.cast::<NextSlot<O, F>>().as_ptr());
+    let complete = slot.complete.take().unwrap();
+    drop(slot);
+    complete(more);
+}
@@ -0,0 +1,384 @@
+use super::utils::ForwardError;
+use super::utils::{complete, complete_sequence, forward_error, link_stop, start_next};
+use crate::factories::{just, Just};
+use exec_core::receiver::{GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::sequence::{NextReceiver, SetNext};
+use exec_core::{OperationState, Sender, SequenceSender, StopCallback, StopSource, StopToken};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Pairs the items of `left` and `right` by position, the sequence ends with
+/// the shorter of the two and the other one is then stopped.
+pub fn zip<A, B, TA, TB>(left: A, right: B) -> Zip<A, B, TA, TB> {
+    Zip::new(left, right)
+}
+
+pub struct Zip<A, B, TA, TB> {
+    left: A,
+    right: B,
+    _phantom: PhantomData<(TA, TB)>,
+}
+
+impl<A, B, TA, TB> Zip<A, B, TA, TB> {
+    pub fn new(left: A, right: B) -> Self {
+        Self {
+            left,
+            right,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+pub struct Left;
+
+pub struct Right;
+
+struct State<TA, TB, R> {
+    inner: Mutex<Inner<TA, TB, R>>,
+    /// Stops both sequences.
+    stop_source: StopSource,
+}
+
+struct Inner<TA, TB, R> {
+    receiver: Option<R>,
+    /// An item waiting for the item of the other sequence.
+    left: Option<(TA, NextReceiver)>,
+    right: Option<(TB, NextReceiver)>,
+    left_done: bool,
+    right_done: bool,
+    error: Option<ForwardError<R>>,
+}
+
+impl<TA, TB, R> State<TA, TB, R>
+where
+    R: SetNext<Just<(TA, TB)>>,
+{
+    fn push_left(&self, value: TA, next: NextReceiver) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.right_done {
+            drop(inner);
+            return next.set_stopped();
+        }
+        match inner.right.take() {
+            Some((right, right_next)) => Self::pass(inner, (value, right), next, right_next),
+            None => inner.left = Some((value, next)),
+        }
+    }
+
+    fn push_right(&self, value: TB, next: NextReceiver) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.left_done {
+            drop(inner);
+            return next.set_stopped();
+        }
+        match inner.left.take() {
+            Some((left, left_next)) => Self::pass(inner, (left, value), left_next, next),
+            None => inner.right = Some((value, next)),
+        }
+    }
+
+    /// Passes a pair on, both items are consumed once it is.
+    fn pass(
+        mut inner: MutexGuard<'_, Inner<TA, TB, R>>,
+        pair: (TA, TB),
+        left: NextReceiver,
+        right: NextReceiver,
+    ) {
+        let next = inner.receiver.as_mut().unwrap().set_next(just(pair));
+        drop(inner);
+        let stop_token = left.get_stop_token();
+        start_next(next, stop_token, move |more| {
+            complete(left, more);
+            complete(right, more);
+        });
+    }
+}
+
+impl<TA, TB, R> State<TA, TB, R>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn finish(&self, left: bool, error: Option<ForwardError<R>>) {
+        self.stop_source.request_stop();
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.error = inner.error.take().or(error);
+        // The item of the other sequence won't be paired anymore.
+        let pending = if left {
+            inner.left_done = true;
+            inner.right.take().map(|(_, next)| next)
+        } else {
+            inner.right_done = true;
+            inner.left.take().map(|(_, next)| next)
+        };
+        if !(inner.left_done && inner.right_done) {
+            drop(inner);
+            if let Some(next) = pending {
+                next.set_stopped();
+            }
+            return;
+        }
+        let receiver = inner.receiver.take().unwrap();
+        let error = inner.error.take();
+        drop(inner);
+        complete_sequence(receiver, error);
+    }
+}
+
+pub struct ZipReceiver<TA, TB, R, Side> {
+    state: Arc<State<TA, TB, R>>,
+    _side: PhantomData<Side>,
+}
+
+impl<S, TA, TB, R, Side> SetNext<S> for ZipReceiver<TA, TB, R, Side>
+where
+    S: Sender<ZipItemReceiver<TA, TB, R, Side>>,
+{
+    type Next = ZipNext<S, TA, TB, R, Side>;
+
+    fn set_next(&mut self, item: S) -> Self::Next {
+        ZipNext {
+            item,
+            state: self.state.clone(),
+            _side: PhantomData,
+        }
+    }
+}
+
+impl<TA, TB, R> SetValue for ZipReceiver<TA, TB, R, Left>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.state.finish(true, None);
+    }
+}
+
+impl<TA, TB, R> SetValue for ZipReceiver<TA, TB, R, Right>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    type Value = ();
+
+    fn set_value(self, _value: Self::Value) {
+        self.state.finish(false, None);
+    }
+}
+
+impl<TA, TB, R> SetError for ZipReceiver<TA, TB, R, Left>
+where
+    R: SetValue<Value = ()> + SetError + SetStopped + GetStopToken,
+    R::Error: Send + 'static,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.state.finish(true, Some(forward_error(error)));
+    }
+}
+
+impl<TA, TB, R> SetError for ZipReceiver<TA, TB, R, Right>
+where
+    R: SetValue<Value = ()> + SetError + SetStopped + GetStopToken,
+    R::Error: Send + 'static,
+{
+    type Error = R::Error;
+
+    fn set_error(self, error: Self::Error) {
+        self.state.finish(false, Some(forward_error(error)));
+    }
+}
+
+impl<TA, TB, R> SetStopped for ZipReceiver<TA, TB, R, Left>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn set_stopped(self) {
+        self.state.finish(true, None);
+    }
+}
+
+impl<TA, TB, R> SetStopped for ZipReceiver<TA, TB, R, Right>
+where
+    R: SetValue<Value = ()> + SetStopped + GetStopToken,
+{
+    fn set_stopped(self) {
+        self.state.finish(false, None);
+    }
+}
+
+impl<TA, TB, R, Side> GetStopToken for ZipReceiver<TA, TB, R, Side> {
+    fn get_stop_token(&self) -> StopToken {
+        self.state.stop_source.token()
+    }
+}
+
+/// Runs an item, then waits for the item of the other sequence.
+pub struct ZipNext<S, TA, TB, R, Side> {
+    item: S,
+    state: Arc<State<TA, TB, R>>,
+    _side: PhantomData<Side>,
+}
+
+impl<S, TA, TB, R, Side> Sender<NextReceiver> for ZipNext<S, TA, TB, R, Side>
+where
+    S: Sender<ZipItemReceiver<TA, TB, R, Side>>,
+{
+    type Value = ();
+    type Error = ();
+
+    type Operation = S::Operation;
+
+    fn connect(self, receiver: NextReceiver) -> Self::Operation {
+        self.item.connect(ZipItemReceiver {
+            state: self.state,
+            receiver,
+            _side: PhantomData,
+        })
+    }
+}
+
+pub struct ZipItemReceiver<TA, TB, R, Side> {
+    state: Arc<State<TA, TB, R>>,
+    receiver: NextReceiver,
+    _side: PhantomData<Side>,
+}
+
+impl<TA, TB, R> SetValue for ZipItemReceiver<TA, TB, R, Left>
+where
+    R: SetNext<Just<(TA, TB)>>,
+{
+    type Value = TA;
+
+    fn set_value(self, value: Self::Value) {
+        self.state.push_left(value, self.receiver);
+    }
+}
+
+impl<TA, TB, R> SetValue for ZipItemReceiver<TA, TB, R, Right>
+where
+    R: SetNext<Just<(TA, TB)>>,
+{
+    type Value = TB;
+
+    fn set_value(self, value: Self::Value) {
+        self.state.push_right(value, self.receiver);
+    }
+}
+
+impl<TA, TB, R, Side> SetStopped for ZipItemReceiver<TA, TB, R, Side> {
+    fn set_stopped(self) {
+        self.receiver.set_stopped();
+    }
+}
+
+impl<TA, TB, R, Side> GetStopToken for ZipItemReceiver<TA, TB, R, Side> {
+    fn get_stop_token(&self) -> StopToken {
+        self.receiver.get_stop_token()
+    }
+}
+
+pub struct ZipOperation<A, B, TA, TB, R> {
+    left: A,
+    right: B,
+    state: Arc<State<TA, TB, R>>,
+    stop_callback: Option<StopCallback>,
+}
+
+impl<A, B, TA, TB, R> OperationState for ZipOperation<A, B, TA, TB, R>
+where
+    A: OperationState,
+    B: OperationState,
+    R: GetStopToken,
+{
+    fn start(&mut self) {
+        let token = {
+            let inner = self.state.inner.lock().unwrap();
+            inner.receiver.as_ref().unwrap().get_stop_token()
+        };
+        self.stop_callback = Some(link_stop(&token, &self.state.stop_source));
+        self.left.start();
+        self.right.start();
+    }
+}
+
+impl<A, B, TA, TB, R> SequenceSender<R> for Zip<A, B, TA, TB>
+where
+    A: SequenceSender<ZipReceiver<TA, TB, R, Left>>,
+    B: SequenceSender<ZipReceiver<TA, TB, R, Right>>,
+    R: GetStopToken,
+{
+    type Error = A::Error;
+
+    type Operation = ZipOperation<A::Operation, B::Operation, TA, TB, R>;
+
+    fn subscribe(self, receiver: R) -> Self::Operation {
+        let state = Arc::new(State {
+            inner: Mutex::new(Inner {
+                receiver: Some(receiver),
+                left: None,
+                right: None,
+                left_done: false,
+                right_done: false,
+                error: None,
+            }),
+            stop_source: StopSource::new(),
+        });
+        ZipOperation {
+            left: self.left.subscribe(ZipReceiver {
+                state: state.clone(),
+                _side: PhantomData,
+            }),
+            right: self.right.subscribe(ZipReceiver {
+                state: state.clone(),
+                _side: PhantomData,
+            }),
+            state,
+            stop_callback: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequence::{interval, iterate, transform_each};
+    use crate::then;
+    use exec_executor::SingleThreadContext;
+    use exec_test::receivers::{CollectReceiver, Collected};
+    use std::time::Duration;
+
+    #[test]
+    fn test_zip() {
+        let (receiver, collected) = CollectReceiver::new();
+        zip(iterate(1..), iterate(["a", "b"]))
+            .subscribe(receiver)
+            .start();
+        assert_eq!(
+            collected.recv().unwrap(),
+            Collected::Value(vec![(1, "a"), (2, "b")])
+        );
+    }
+
+    #[test]
+    fn test_zip_on_context() {
+        let context = SingleThreadContext::new();
+        let ticks = interval(context.get_scheduler(), Duration::from_millis(1));
+        let mut count = 0;
+        let ticks = transform_each(ticks, move |tick| {
+            count += 1;
+            let count = count;
+            then(tick, move |()| count)
+        });
+
+        let (receiver, collected) = CollectReceiver::new();
+        let mut op = zip(iterate(["a", "b", "c"]), ticks).subscribe(receiver);
+        op.start();
+        assert_eq!(
+            collected.recv().unwrap(),
+            Collected::Value(vec![("a", 1), ("b", 2), ("c", 3)])
+        );
+    }
+}
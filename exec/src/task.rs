@@ -0,0 +1,121 @@
+use crate::consumers::submit::SubmitReceiver;
+use crate::factories::{FromFuture, FromFutureOperation, PollReceiver};
+use exec_core::receiver::{GetScheduler, GetStopToken, SetError, SetStopped, SetValue};
+use exec_core::{Scheduler, Sender};
+use std::future::Future;
+use std::pin::Pin;
+
+type TaskFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send>>;
+
+/// A coroutine that is itself a sender.
+///
+/// The wrapped future can `.await` other senders through
+/// [`IntoAwaitable`](crate::IntoAwaitable). It is polled on the scheduler of
+/// the receiver it is connected to, so it always resumes there whatever
+/// thread the awaited senders complete on. Stop requested through the
+/// receiver is forwarded to the sender being awaited, an `Err` output
+/// completes the error channel.
+pub struct Task<T, E> {
+    future: TaskFuture<T, E>,
+}
+
+impl<T, E> Task<T, E> {
+    pub fn new<F>(future: F) -> Self
+    where
+        F: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        Self {
+            future: Box::pin(future),
+        }
+    }
+}
+
+impl<T, E, R> Sender<R> for Task<T, E>
+where
+    T: 'static,
+    E: 'static,
+    R: SetValue<Value = T>
+        + SetError<Error = E>
+        + SetStopped
+        + GetStopToken
+        + GetScheduler
+        + Send
+        + 'static,
+    R::Scheduler:
+        Scheduler<SubmitReceiver<PollReceiver<R::Scheduler, TaskFuture<T, E>, R>>> + 'static,
+{
+    type Value = T;
+    type Error = E;
+
+    type Operation = FromFutureOperation<R::Scheduler, TaskFuture<T, E>, R>;
+
+    fn connect(self, receiver: R) -> Self::Operation {
+        FromFuture::new(self.future).connect(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consumers::{sync_wait, sync_wait_for, SyncWaitError};
+    use crate::factories::just_error;
+    use crate::{just, then, IntoAwaitable};
+    use exec_executor::SingleThreadContext;
+    use exec_test::errors::TestError;
+    use exec_test::senders::NeverSender;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_task() {
+        let task = Task::new(async {
+            let x = just(1).into_awaitable().await.unwrap().unwrap();
+            let y = then(just(x), |x| x * 2).into_awaitable().await.unwrap();
+            Ok::<_, TestError>(y.unwrap() + 1)
+        });
+        assert_eq!(sync_wait(task), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_task_resumes_on_scheduler() {
+        let context = SingleThreadContext::new();
+        let mut scheduler = context.get_scheduler();
+        let task = Task::new(async move {
+            let other = then(scheduler.schedule(), |_| thread::current().id())
+                .into_awaitable()
+                .await
+                .unwrap();
+            Ok::<_, TestError>((other.unwrap(), thread::current().id()))
+        });
+
+        let (other, resumed) = sync_wait(task).unwrap().unwrap();
+        assert_ne!(other, thread::current().id());
+        assert_eq!(resumed, thread::current().id());
+    }
+
+    #[test]
+    fn test_task_error() {
+        let task = Task::new(async {
+            then(just_error(TestError), |_: ()| 1)
+                .into_awaitable()
+                .await?;
+            unreachable!();
+        });
+        assert_eq!(sync_wait::<_, (), _>(task), Err(TestError));
+    }
+
+    #[test]
+    fn test_task_stop_propagates() {
+        let sender = NeverSender::<i32>::new();
+        let stopped = sender.stopped();
+        let task = Task::new(async move {
+            let value = sender.into_awaitable().await.unwrap();
+            Ok::<_, TestError>(value)
+        });
+
+        let result = sync_wait_for(task, Duration::from_millis(10));
+        assert_eq!(result, Err(SyncWaitError::TimedOut));
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+}